@@ -1,16 +1,140 @@
 // Copyright by Owen O'Malley 2024
 
-use criterion::{criterion_group, criterion_main};
-extern crate num_derive;
+use criterion::Criterion;
+use std::time::SystemTime;
+use rust_bench::machine::{self, Fingerprint};
+use rust_bench::noise;
+use rust_bench::results::{self, Measurement, Run};
+use rust_bench::select::Selection;
 
+mod counted;
+mod cpp;
 mod dispatch;
 mod dual;
 mod branching;
 mod elements;
+mod instructions;
 mod option;
+mod select;
 mod sliding;
+mod sweep;
 mod tail;
 
-criterion_group!(benches, branching::benchmark, dispatch::benchmark, dual::benchmark,
-  elements::benchmark, option::benchmark, sliding::benchmark, tail::benchmark);
-criterion_main!(benches);
\ No newline at end of file
+type Category = (&'static str, fn(&mut Criterion));
+
+/// The categories of benchmarks, which are the modules, by name.
+const CATEGORIES: &[Category] = &[("branching", branching::benchmark),
+  ("cpp", cpp::benchmark), ("dispatch", dispatch::benchmark), ("dual", dual::benchmark),
+  ("elements", elements::benchmark), ("option", option::benchmark),
+  ("sliding", sliding::benchmark), ("tail", tail::benchmark)];
+
+/// Run the selected categories, which skips generating the data for the
+/// others.
+fn benches() {
+  let mut criterion = Criterion::default().configure_from_args();
+  for (name, benchmark) in CATEGORIES {
+    if select::start_category(name) {
+      benchmark(&mut criterion);
+    }
+  }
+}
+
+fn main() {
+  let selection = match Selection::from_env() {
+    Ok(selection) => selection,
+    Err(err) => {
+      eprintln!("{err}");
+      std::process::exit(1);
+    }
+  };
+  if let Err(err) = rust_bench::seed_from_env() {
+    eprintln!("{err}");
+    std::process::exit(1);
+  }
+  let listing = selection.list;
+  if let Some(unknown) = selection.categories.iter()
+      .find(|c| !CATEGORIES.iter().any(|(name, _)| name == c)) {
+    eprintln!("Unknown category {unknown}");
+    std::process::exit(1);
+  }
+  select::init(selection);
+  if listing {
+    benches();
+    select::print_list();
+    return;
+  }
+  match instructions::init() {
+    Ok(true) => return count_instructions(),
+    Ok(false) => {}
+    Err(err) => {
+      eprintln!("{err}");
+      std::process::exit(1);
+    }
+  }
+  // Pin the thread and check the machine before anything is timed.
+  let noise = noise::prepare();
+  println!("Noise: {}", noise.summary());
+  for warning in noise.warnings() {
+    eprintln!("Noisy machine: {warning}");
+  }
+  if noise.strict && noise.is_noisy() {
+    eprintln!("Refusing to run on a noisy machine because RUST_BENCH_STRICT is set");
+    std::process::exit(1);
+  }
+  let started = SystemTime::now();
+  // Record the machine next to Criterion's results.
+  let fingerprint = Fingerprint::collect();
+  println!("Machine: {}", fingerprint.summary());
+  let path = machine::recorded_path(&rust_bench::criterion_home());
+  if let Err(err) = fingerprint.write(&path) {
+    eprintln!("Can't write {}: {err}", path.display());
+  }
+
+  benches();
+  Criterion::default().configure_from_args().final_summary();
+
+  // Export the benchmarks that this run measured.
+  match results::collect(&rust_bench::criterion_home(), Some(started)) {
+    Ok(benchmarks) if benchmarks.is_empty() => {}
+    Ok(mut benchmarks) => {
+      let mut counters = counted::take();
+      for bench in &mut benchmarks {
+        bench.counters = counters.remove(&bench.id);
+        if let Some(counters) = bench.counters {
+          println!("{}: {counters}", bench.id);
+        }
+      }
+      let mut run = Run::new(started, results::config_from_env(), rust_bench::data_seed(),
+                             fingerprint, Measurement::WallTime, benchmarks);
+      run.noise = Some(noise);
+      match run.save() {
+        Ok(dir) => println!("Saved results in {}", dir.display()),
+        Err(err) => eprintln!("Can't save results: {err}"),
+      }
+    }
+    Err(err) => eprintln!("Can't collect results: {err}"),
+  }
+}
+
+/// Count the instructions of the selected benchmarks and save them as a
+/// run. The counts don't depend on the machine's noise, so it isn't
+/// checked.
+fn count_instructions() {
+  let started = SystemTime::now();
+  benches();
+  match instructions::take() {
+    Ok(benchmarks) if benchmarks.is_empty() => eprintln!("No benchmarks were counted"),
+    Ok(benchmarks) => {
+      let run = Run::new(started, results::config_from_env(), rust_bench::data_seed(),
+                         Fingerprint::collect(), Measurement::Instructions, benchmarks);
+      match run.save() {
+        Ok(dir) => println!("Saved results in {}", dir.display()),
+        Err(err) => eprintln!("Can't save results: {err}"),
+      }
+    }
+    Err(err) => {
+      eprintln!("{err}");
+      std::process::exit(1);
+    }
+  }
+}
@@ -0,0 +1,157 @@
+// Copyright by Owen O'Malley 2024
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::Mutex;
+use criterion::{BenchmarkGroup, BenchmarkId, Criterion, Throughput};
+use criterion::measurement::WallTime;
+use rust_bench::perf::{CounterSet, Counters};
+use crate::{instructions, select};
+
+// Time the benchmarks with Criterion and, when $RUST_BENCH_COUNTERS is
+// set, count the hardware events of each benchmark in a separate pass
+// the first time that Criterion calls it. Criterion never calls the
+// benchmarks that don't match its filter, so they aren't counted either.
+// With $RUST_BENCH_INSTRUCTIONS set, it counts the instructions of each
+// benchmark instead of calling Criterion.
+//
+// Each benchmark builds its input with a closure only once it is
+// selected, so that skipped and listed benchmarks never generate their
+// data. The benchmarks that share an input hold it in a LazyCell.
+
+/// The number of elements to process in each counting pass.
+const COUNT_ELEMENTS: u64 = 10_000_000;
+
+enum Counting {
+  Unknown,
+  Off,
+  On(CounterSet),
+}
+
+static COUNTING: Mutex<Counting> = Mutex::new(Counting::Unknown);
+static RESULTS: Mutex<BTreeMap<String, Counters>> = Mutex::new(BTreeMap::new());
+
+/// Count the events of the benchmark if counting is on and it hasn't
+/// been counted yet.
+fn count<R>(id: &str, elements: u64, f: impl FnMut() -> R) {
+  let mut counting = COUNTING.lock().unwrap();
+  if let Counting::Unknown = *counting {
+    *counting = match std::env::var_os("RUST_BENCH_COUNTERS") {
+      None => Counting::Off,
+      Some(_) => match CounterSet::open() {
+        Ok(set) => Counting::On(set),
+        Err(err) => {
+          eprintln!("Hardware counters are unavailable, so only times are reported: {err}");
+          Counting::Off
+        }
+      },
+    };
+  }
+  let Counting::On(set) = &mut *counting else {
+    return;
+  };
+  let mut results = RESULTS.lock().unwrap();
+  if results.contains_key(id) {
+    return;
+  }
+  match set.measure((COUNT_ELEMENTS / elements.max(1)).max(1), elements, f) {
+    Ok(counters) => {
+      results.insert(id.to_string(), counters);
+    }
+    Err(err) => eprintln!("Can't count {id}: {err}"),
+  }
+}
+
+/// Take the counters of the benchmarks that were counted by their ids.
+pub fn take() -> BTreeMap<String, Counters> {
+  std::mem::take(&mut *RESULTS.lock().unwrap())
+}
+
+/// Benchmark f on the input, which processes the given number of
+/// elements, outside of a group.
+pub fn bench_function<I, R>(c: &mut Criterion, id: &str, elements: u64, input: impl FnOnce() -> I,
+                            f: impl Fn(&I) -> R) {
+  if !select::wants(id, None, None) {
+    return;
+  }
+  let input = input();
+  if instructions::active() {
+    return instructions::measure(id, id, None, None, elements, || f(&input));
+  }
+  c.bench_function(id, |b| {
+    b.iter(|| f(&input));
+    count(id, elements, || f(&input));
+  });
+}
+
+/// A benchmark group that also counts the events of its benchmarks.
+pub struct Group<'a> {
+  name: String,
+  elements: u64,
+  group: BenchmarkGroup<'a, WallTime>,
+}
+
+impl<'a> Group<'a> {
+  /// Create a group whose benchmarks each process the given number of
+  /// elements.
+  pub fn new(c: &'a mut Criterion, name: impl Into<String>, elements: u64) -> Self {
+    let name = name.into();
+    let mut group = c.benchmark_group(name.clone());
+    group.throughput(Throughput::Elements(elements));
+    Group { name, elements, group }
+  }
+
+  /// Change the number of elements for the following benchmarks.
+  pub fn set_elements(&mut self, elements: u64) {
+    self.elements = elements;
+    self.group.throughput(Throughput::Elements(elements));
+  }
+
+  pub fn sample_size(&mut self, n: usize) {
+    self.group.sample_size(n);
+  }
+
+  /// Benchmark f on the input with the id "group/function/parameter".
+  pub fn bench<I, R>(&mut self, function: &str, parameter: impl Display,
+                     input: impl FnOnce() -> I, f: impl Fn(&I) -> R) {
+    let parameter = parameter.to_string();
+    if !select::wants(&self.name, Some(function), Some(&parameter)) {
+      return;
+    }
+    let id = format!("{}/{function}/{parameter}", self.name);
+    let input = input();
+    if instructions::active() {
+      return instructions::measure(&id, &self.name, Some(function), Some(&parameter),
+                                   self.elements, || f(&input));
+    }
+    self.run(&id, BenchmarkId::new(function, parameter), &input, f);
+  }
+
+  /// Benchmark f on the input with the id "group/parameter".
+  pub fn bench_parameter<I, R>(&mut self, parameter: impl Display, input: impl FnOnce() -> I,
+                               f: impl Fn(&I) -> R) {
+    let parameter = parameter.to_string();
+    if !select::wants(&self.name, None, Some(&parameter)) {
+      return;
+    }
+    let id = format!("{}/{parameter}", self.name);
+    let input = input();
+    if instructions::active() {
+      return instructions::measure(&id, &self.name, None, Some(&parameter), self.elements,
+                                   || f(&input));
+    }
+    self.run(&id, BenchmarkId::from_parameter(parameter), &input, f);
+  }
+
+  fn run<I, R>(&mut self, id: &str, bench_id: BenchmarkId, input: &I, f: impl Fn(&I) -> R) {
+    let elements = self.elements;
+    self.group.bench_with_input(bench_id, input, |b, input| {
+      b.iter(|| f(input));
+      count(id, elements, || f(input));
+    });
+  }
+
+  pub fn finish(self) {
+    self.group.finish();
+  }
+}
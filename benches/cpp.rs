@@ -0,0 +1,44 @@
+// Copyright by Owen O'Malley 2024
+
+use criterion::{black_box, Criterion};
+use std::cell::LazyCell;
+use rust_bench::cpp;
+use rust_bench::patterns::Layout;
+use crate::counted;
+
+// Run the C++ kernels over the same inputs as the Rust benchmarks, so
+// that the two languages can be compared in the same run.
+
+const SIZE: usize = 10_000;
+
+pub fn benchmark(c: &mut Criterion) {
+  let seed = rust_bench::data_seed();
+  for layout in [Layout::Random, Layout::TickTock] {
+    let mut group = counted::Group::new(c, format!("cpp dispatch {layout}"), SIZE as u64);
+    for number_of_classes in 1..=cpp::CLASSES {
+      let ids = LazyCell::new(|| {
+        let ids: Vec<i32> = layout.generate(number_of_classes, SIZE, seed).into_iter()
+            .map(|x| x as i32).collect();
+        let objs = cpp::Processors::new(&ids);
+        cpp::check_dispatch(&ids, &objs);
+        (ids, objs)
+      });
+      group.bench("objs", number_of_classes, || &ids.1, |objs| black_box(objs).iter_objs());
+      group.bench("switch", number_of_classes, || &ids.0[..],
+                  |ids| cpp::switch_dispatch(black_box(ids)));
+    }
+    group.finish();
+  }
+
+  let array = LazyCell::new(|| {
+    let array: [i32; SIZE] = rust_bench::random_array(-100_000..100_000, seed);
+    cpp::check_elements(&array);
+    array
+  });
+  counted::bench_function(c, "cpp element index", SIZE as u64, || &array[..],
+                          |array| cpp::sum_via_index(black_box(array)));
+  counted::bench_function(c, "cpp element for", SIZE as u64, || &array[..],
+                          |array| cpp::sum_via_for(black_box(array)));
+  counted::bench_function(c, "cpp element accumulate", SIZE as u64, || &array[..],
+                          |array| cpp::sum_via_accumulate(black_box(array)));
+}
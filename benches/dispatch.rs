@@ -1,236 +1,279 @@
 // Copyright by Owen O'Malley 2024
 
 use criterion::{black_box, Criterion};
-use num_derive::FromPrimitive;
-use paste::paste;
-use rand::SeedableRng;
-use rand::seq::SliceRandom;
-use rand_chacha::ChaChaRng;
+use std::cell::LazyCell;
+use std::fmt::Display;
+use rust_bench::kernels::dispatch::*;
+use rust_bench::kernels::interpreter::{self, Program, OPCODES, OP_FUNCS};
+use rust_bench::patterns::Layout;
+use crate::counted;
 
-const SIZE: usize = 10_000;
-
-/// A utility function to translate the digits via a match
-fn map_digit(v: i32) -> i32 {
-  match v {
-    0 => 1,
-    1 => 4,
-    2 => 3,
-    3 => 5,
-    4 => 7,
-    5 => 11,
-    6 => 13,
-    7 => 17,
-    8 => 19,
-    9 => 23,
-    _ => panic!("Bad digit {v}"),
-  }
-}
-
-/// Use a passed in function to translate
-fn iter_func(data: &[i32], func: fn(i32) -> i32) -> i32 {
-  data.iter().map(|v| func(*v)).sum()
-}
-
-/// Use templates to remove the function dispatch costs.
-fn iter_func_template<F>(data: &[i32], func: F) -> i32
-  where F: Fn(i32) -> i32 {
-  data.iter().map(|v| func(*v)).sum()
-}
-
-/// Iterate and dispatch via an array of lambdas.
-fn iter_lambdas(data: &[i32], funcs: &[fn() -> i32]) -> i32 {
-  data.iter().map(|v| funcs[*v as usize]()).sum()
-}
+// Compare the different forms of dispatch. The kernels are in
+// rust_bench::kernels::dispatch.
 
-trait Processor {
-  fn process(&self) -> i32;
-}
-
-/// Define a class that implements the trait and can
-/// handle all the inputs.
-#[derive(Debug)]
-struct GeneralProcessor {
-  x: i32,
-}
-
-impl GeneralProcessor {
-  fn from(i: i32) -> Self {
-    GeneralProcessor {x: map_digit(i)}
-  }
-}
-
-impl Processor for GeneralProcessor {
-  fn process(&self) -> i32 {
-    self.x
-  }
-}
+const SIZE: usize = 10_000;
 
-macro_rules! define_structs {
-    ( $({$id:literal, $value:literal}),* ) => {
-      paste!{
-
-        $(// Define the struct Processor<id> and its implementation
-          struct [<Processor $id>] {
-            // nothing
-          }
-
-          impl Processor for [<Processor $id>] {
-            fn process(&self) -> i32 {
-              $value
-            }
-        })*
-
-        fn processor_from_i32(i: i32) -> Box<dyn Processor> {
-          match i {
-            $($id => Box::new([<Processor $id>]{}) as Box<dyn Processor>,)*
-            _ => panic!("Bad name {i}"),
-          }
-        }
-
-        // Define the equivalent enum
-        #[derive(FromPrimitive)]
-        enum BigEnum {
-          $([<Value $id>],)*
-        }
-
-        impl Processor for BigEnum {
-          fn process(&self) -> i32 {
-            match self {
-              $(BigEnum::[<Value $id>] => $value,)*
-            }
-          }
-        }
-      }
-    }
+/// Create SIZE objects with the given layout of classes.
+fn objs_from_layout(layout: Layout, classes: usize, seed: u64) -> Vec<Box<dyn Processor>> {
+  let ids = ids_from_layout(layout, classes, SIZE, seed);
+  check_classes(&ids);
+  objs_from_ids(&ids)
 }
 
-// Define Processor0 to Processor23 using a macro.
-define_structs!({0, 1}, {1, 4}, {2, 3}, {3, 5},
-  {4, 7}, {5, 11}, {6, 13}, {7, 17}, {8, 19},
-  {9, 23}, {10, 25}, {11, 27}, {12, 29}, {13, 31},
-  {14, 33}, {15, 35}, {16, 37}, {17, 39}, {18, 41},
-  {19, 43}, {20, 45}, {21, 47}, {22, 49}, {23, 51},
-  {24, 53}, {25, 55}, {26, 57}, {27, 59}, {28, 61},
-  {29, 63}, {30, 65}, {31, 67}, {32, 69}, {33, 71},
-  {34, 73}, {35, 75}, {36, 77}, {37, 79}, {38, 81},
-  {39, 83}, {40, 85}, {41, 87}, {42, 89}, {43, 91},
-  {44, 93}, {45, 95}, {46, 97}, {47, 99}, {48, 101},
-  {49, 103});
-
-/// Use virtual dispatch through the trait.
-fn iter_objs(data: &[Box<dyn Processor>]) -> i32 {
-  data.iter().map(|v| v.process()).sum()
+/// Benchmark the other pointer types and vtable shapes over the class
+/// ids, which the caller has checked.
+fn bench_forms<F: FnOnce() -> Vec<i32>>(group: &mut counted::Group, parameter: impl Display,
+                                        ids: &LazyCell<Vec<i32>, F>) {
+  let objs = LazyCell::new(|| objs_from_ids(ids));
+  group.bench("ref objs", &parameter,
+              || objs.iter().map(|o| o.as_ref()).collect::<Vec<&dyn Processor>>(),
+              |refs| iter_refs(black_box(refs)));
+  group.bench("rc objs", &parameter, || rcs_from_ids(ids), |objs| iter_pointers(black_box(objs)));
+  group.bench("arc objs", &parameter, || arcs_from_ids(ids),
+              |objs| iter_pointers(black_box(objs)));
+  group.bench("closures", &parameter, || closures_from_ids(ids),
+              |closures| iter_closures(black_box(closures)));
+  group.bench("impl objs", &parameter, || impl_objs_from_ids(ids),
+              |objs| template_objs(black_box(objs)));
+  group.bench("multi objs", &parameter, || multi_objs_from_ids(ids),
+              |objs| iter_multi_objs(black_box(objs)));
 }
 
-/// Use a template to inline the method call.
-fn template_objs<T: Processor>(data: &[T]) -> i32 {
-  data.iter().map(|v| v.process()).sum()
+/// Benchmark the payload enum E against the equivalent trait objects
+/// with random variants.
+fn bench_payloads<E: PayloadEnum<N>, const N: usize, F: FnOnce() -> Vec<u32>>(
+    group: &mut counted::Group, data: &LazyCell<Vec<u32>, F>, seed: u64) {
+  let ids = LazyCell::new(|| {
+    let ids = rust_bench::random_vec_in(0..E::VARIANTS as i32, SIZE, seed);
+    check_payloads::<E, N>(&ids, data);
+    ids
+  });
+  group.bench(&format!("enum {N}w"), E::VARIANTS, || payload_enums::<E, N>(&ids, data),
+              |enums| iter_enum(black_box(enums)));
+  group.bench(&format!("objs {N}w"), E::VARIANTS, || payload_objs::<E, N>(&ids, data),
+              |objs| iter_objs(black_box(objs)));
 }
 
-/// Test the impact of wrapping the objects with Box in a template.
-fn template_box_objs<T: Processor>(data: &[Box<T>]) -> i32 {
-  data.iter().map(|v| v.process()).sum()
+/// Benchmark the execution loops of the interpreter on the program.
+fn bench_interpreter<F: FnOnce() -> Program>(group: &mut counted::Group, parameter: impl Display,
+                                             program: &LazyCell<Program, F>) {
+  group.bench("match", &parameter, || &**program,
+              |program| interpreter::run_match(black_box(program)));
+  group.bench("table", &parameter, || &**program,
+              |program| interpreter::run_table(black_box(program), black_box(&OP_FUNCS)));
+  group.bench("vtable", &parameter, || interpreter::decode_vtables(program),
+              |program| interpreter::run_vtables(black_box(program)));
+  group.bench("closures", &parameter, || interpreter::compile_closures(program),
+              |program| interpreter::run_closures(black_box(program)));
 }
 
-#[derive(FromPrimitive)]
-enum ProcessorEnum {
-  Zero, One, Two, Three, Four, Five, Six, Seven, Eight, Nine,
+/// Create the program with the layout of opcodes, which is checked once
+/// it is built.
+fn lazy_program(layout: Layout, opcodes: usize, seed: u64)
+    -> LazyCell<Program, impl FnOnce() -> Program> {
+  LazyCell::new(move || {
+    let program = interpreter::program_from_layout(layout, opcodes, SIZE, seed);
+    interpreter::check_program(&program);
+    program
+  })
 }
 
-impl Processor for ProcessorEnum {
-  fn process(&self) -> i32 {
-    match self {
-      ProcessorEnum::Zero => 1,
-      ProcessorEnum::One => 4,
-      ProcessorEnum::Two => 3,
-      ProcessorEnum::Three => 5,
-      ProcessorEnum::Four => 7,
-      ProcessorEnum::Five => 11,
-      ProcessorEnum::Six => 13,
-      ProcessorEnum::Seven => 17,
-      ProcessorEnum::Eight => 19,
-      ProcessorEnum::Nine => 23,
-    }
-  }
+/// Create the class ids with the layout, which are checked once they are
+/// built.
+fn lazy_ids(layout: Layout, classes: usize, seed: u64)
+    -> LazyCell<Vec<i32>, impl FnOnce() -> Vec<i32>> {
+  LazyCell::new(move || {
+    let ids = ids_from_layout(layout, classes, SIZE, seed);
+    check_classes(&ids);
+    ids
+  })
 }
 
-#[derive(FromPrimitive)]
-enum Enum3 {
-  Value0, Value1, Value2,
-}
-
-impl Processor for Enum3 {
-  fn process(&self) -> i32 {
-    match self {
-      Enum3::Value0 => 1,
-      Enum3::Value1 => 4,
-      Enum3::Value2 => 3,
-    }
-  }
-}
-
-fn iter_enum<T: Processor>(data: &[T]) -> i32 {
-  data.iter().map(|x| x.process()).sum()
+/// Benchmark the layouts of the objects for the class ids: boxed, in a
+/// type sorted arena, inline with a function pointer, inline in an enum,
+/// and in a Vec per class.
+fn bench_layouts<F: FnOnce() -> Vec<i32>>(group: &mut counted::Group, parameter: usize,
+                                          ids: &LazyCell<Vec<i32>, F>) {
+  group.bench("boxed", parameter, || stateful_objs_from_ids(ids),
+              |objs| iter_objs(black_box(objs)));
+  let arena = LazyCell::new(|| Arena::from_ids(ids));
+  group.bench("arena sorted", parameter, || arena.objs(), |objs| iter_refs(black_box(objs)));
+  group.bench("inline fns", parameter, || inline_fns_from_ids(ids),
+              |objs| iter_inline_fns(black_box(objs)));
+  group.bench("inline enum", parameter, || inline_objs_from_ids(ids),
+              |objs| iter_enum(black_box(objs)));
+  group.bench("per type", parameter, || Archetypes::from_ids(ids),
+              |archetypes| black_box(archetypes).process());
 }
 
 pub fn benchmark(c: &mut Criterion) {
-  let array10: [i32; SIZE] = rust_bench::random_array(0..10, 0);
-  let lambdas: [fn() -> i32; 10] = [|| 1, || 4, || 3, || 5, || 7, || 11, || 13, || 17, || 19, || 23];
+  let seed = rust_bench::data_seed();
+  let array10 = LazyCell::new(|| {
+    let array10: [i32; SIZE] = rust_bench::random_array(0..10, seed);
+    check_digits(&array10);
+    array10
+  });
+  let lambdas = DIGIT_LAMBDAS;
 
   // Single functions
-  c.bench_function("dispatch lambda", |b| b.iter(|| iter_func(black_box(&array10), black_box(|i| map_digit(i)))));
-  c.bench_function("dispatch func", |b| b.iter(|| iter_func(black_box(&array10), black_box(map_digit))));
-  c.bench_function("dispatch func template", |b| b.iter(|| iter_func_template(black_box(&array10), black_box(map_digit))));
+  #[allow(clippy::redundant_closure)]
+  counted::bench_function(c, "dispatch lambda", SIZE as u64, || &array10[..],
+                          |array| iter_func(black_box(array), black_box(|i| map_digit(i))));
+  counted::bench_function(c, "dispatch func", SIZE as u64, || &array10[..],
+                          |array| iter_func(black_box(array), black_box(map_digit)));
+  counted::bench_function(c, "dispatch func template", SIZE as u64, || &array10[..],
+                          |array| iter_func_template(black_box(array), black_box(map_digit)));
 
   // Multiple functions
-  c.bench_function("dispatch lambdas", |b| b.iter(|| iter_lambdas(black_box(&array10), black_box(&lambdas))));
-  let mut array10 = array10;
-  array10.sort_unstable();
-  c.bench_function("dispatch sort lambdas", |b| b.iter(|| iter_lambdas(black_box(&array10), black_box(&lambdas))));
-  let obj10= array10.map(|x| GeneralProcessor::from(x));
-  c.bench_function("dispatch template objs", |b| b.iter(|| template_objs(&obj10)));
-  let obj10= array10.map(|x| Box::new(GeneralProcessor::from(x)));
-  c.bench_function("dispatch template box objs", |b| b.iter(|| template_box_objs(&obj10)));
+  counted::bench_function(c, "dispatch lambdas", SIZE as u64, || &array10[..],
+                          |array| iter_lambdas(black_box(array), black_box(&lambdas)));
+  let sorted10 = LazyCell::new(|| {
+    let mut sorted10 = *array10;
+    sorted10.sort_unstable();
+    sorted10
+  });
+  counted::bench_function(c, "dispatch sort lambdas", SIZE as u64, || &sorted10[..],
+                          |array| iter_lambdas(black_box(array), black_box(&lambdas)));
+  counted::bench_function(c, "dispatch template objs", SIZE as u64,
+                          || sorted10.map(GeneralProcessor::from), |objs| template_objs(objs));
+  counted::bench_function(c, "dispatch template box objs", SIZE as u64,
+                          || sorted10.map(|x| Box::new(GeneralProcessor::from(x))),
+                          |objs| template_box_objs(objs));
 
   // Generate SIZE values with a wider range.
-  let big_array: [i32; SIZE] = rust_bench::random_array(0..100_000, 0);
+  let big_array = LazyCell::new(|| {
+    let big_array: [i32; SIZE] = rust_bench::random_array(0..100_000, seed);
+    check_classes(&big_array.map(|x| x % 3));
+    check_classes(&big_array.map(|x| x % 50));
+    big_array
+  });
 
   // Try different sized enums
-  let enums: [Enum3; SIZE] = big_array.map(|x| num_traits::FromPrimitive::from_i32(x % 3)
-      .expect("bad value {x}"));
-  c.bench_function("dispatch enum 3",
-                   |b| b.iter(|| iter_enum(black_box(&enums))));
-  let enums: [ProcessorEnum; SIZE] = big_array
-      .map(|x| num_traits::FromPrimitive::from_i32(x % 10).expect("bad digit"));
-  c.bench_function("dispatch enum 10", |b| b.iter(|| iter_enum(black_box(&enums))));
-  let enums: [BigEnum; SIZE] = big_array.map(|x| num_traits::FromPrimitive::from_i32(x % 50)
-      .expect("bad value {x}"));
-  c.bench_function("dispatch enum 50",
-                   |b| b.iter(|| iter_enum(black_box(&enums))));
+  let mut group = counted::Group::new(c, "dispatch enum", SIZE as u64);
+  group.bench_parameter(3, || -> [Enum3; SIZE] {
+    big_array.map(|x| num_traits::FromPrimitive::from_i32(x % 3)
+        .unwrap_or_else(|| panic!("bad value {x}")))
+  }, |enums| iter_enum(black_box(enums)));
+  group.bench_parameter(10, || -> [ProcessorEnum; SIZE] {
+    big_array.map(|x| num_traits::FromPrimitive::from_i32(x % 10).expect("bad digit"))
+  }, |enums| iter_enum(black_box(enums)));
+  group.bench_parameter(50, || -> [BigEnum; SIZE] {
+    big_array.map(|x| num_traits::FromPrimitive::from_i32(x % 50)
+        .unwrap_or_else(|| panic!("bad value {x}")))
+  }, |enums| iter_enum(black_box(enums)));
+  group.finish();
 
   // Try different numbers of classes with random distributions
+  let mut group = counted::Group::new(c, "dispatch random", SIZE as u64);
   for number_of_classes in (1..=12).chain(50..=50) {
     // Create an array with the right number of classes.
-    let random_objs = big_array
-        .map(|x| processor_from_i32(x % number_of_classes));
-    c.bench_function(format!("dispatch random objs {}", number_of_classes).as_str(),
-                     |b| b.iter(|| iter_objs(black_box(&random_objs))));
+    let ids = lazy_ids(Layout::Random, number_of_classes, seed);
+    group.bench("objs", number_of_classes, || objs_from_ids(&ids),
+                |objs| iter_objs(black_box(objs)));
+    bench_forms(&mut group, number_of_classes, &ids);
   }
+  group.finish();
+
+  // Try the different shapes of data with 10 classes.
+  let mut group = counted::Group::new(c, "dispatch shaped", SIZE as u64);
+  for shape in rust_bench::Shape::ALL {
+    let ids = LazyCell::new(|| {
+      let ids = shape.generate(0..10, SIZE, seed);
+      check_digits(&ids);
+      check_classes(&ids);
+      ids
+    });
+    group.bench("objs", shape, || objs_from_ids(&ids), |objs| iter_objs(black_box(objs)));
+    group.bench("enum", shape, || enums_from_ids::<ProcessorEnum>(&ids),
+                |enums| iter_enum(black_box(enums)));
+    bench_forms(&mut group, shape, &ids);
+  }
+  group.finish();
 
   // Generate a sorted array
-  let sorted_objs: [Box<dyn Processor>; SIZE] = core::array::from_fn(
-      |x| processor_from_i32((x / (big_array.len() / 50)) as i32));
-  c.bench_function("dispatch sorted objs 50",
-                   |b| b.iter(|| iter_objs(black_box(&sorted_objs))));
+  counted::bench_function(c, "dispatch sorted objs 50", SIZE as u64,
+                          || objs_from_layout(Layout::SortedBlocks, 50, seed),
+                          |objs| iter_objs(black_box(objs)));
+  let mut group = counted::Group::new(c, "dispatch sorted", SIZE as u64);
+  bench_forms(&mut group, 50, &lazy_ids(Layout::SortedBlocks, 50, seed));
+  group.finish();
 
   // Try different multiples of 50 for round robin
+  let mut group = counted::Group::new(c, "dispatch rndrn", SIZE as u64);
+  for multiple in [1, 10, 20, 30, 40, 50, 60, 80, 100, 120, 140, 160] {
+    let ids = lazy_ids(Layout::ShuffledWindow { copies: multiple }, 50, seed);
+    group.bench("objs", multiple, || objs_from_ids(&ids), |objs| iter_objs(black_box(objs)));
+    bench_forms(&mut group, multiple, &ids);
+  }
+  group.finish();
+
+  // Separate the pointer chase from the indirect call over the same
+  // sequences of classes as the random and round robin groups.
+  let mut group = counted::Group::new(c, "dispatch layout random", SIZE as u64);
+  for number_of_classes in (1..=12).chain(50..=50) {
+    bench_layouts(&mut group, number_of_classes,
+                  &lazy_ids(Layout::Random, number_of_classes, seed));
+  }
+  group.finish();
+  let mut group = counted::Group::new(c, "dispatch layout rndrn", SIZE as u64);
   for multiple in [1, 10, 20, 30, 40, 50, 60, 80, 100, 120, 140, 160] {
-    let mut order = (0..50*multiple).map(|x| x / multiple).collect::<Vec<i32>>();
-    let mut rng: ChaChaRng = SeedableRng::seed_from_u64(0);
-    order.shuffle(&mut rng);
-    let random_objs: [Box<dyn Processor>; SIZE] = core::array::from_fn(
-      |x| processor_from_i32(order[x % order.len()]));
-    c.bench_function(format!("dispatch rndrn objs {}", multiple).as_str(),
-                       |b| b.iter(|| iter_objs(black_box(&random_objs))));
+    bench_layouts(&mut group, multiple,
+                  &lazy_ids(Layout::ShuffledWindow { copies: multiple }, 50, seed));
+  }
+  group.finish();
+
+  // Find the number of variants and the size of the large variant's
+  // payload where the enums stop beating the trait objects. The
+  // parameter is the number of variants and the functions name the
+  // words in the large variant, while the others have 1, 2, or 4.
+  let mut group = counted::Group::new(c, "dispatch payload", SIZE as u64);
+  let data = LazyCell::new(|| rust_bench::random_vec_of::<u32>(4 * SIZE, seed));
+  bench_payloads::<Payload3<4>, 4, _>(&mut group, &data, seed);
+  bench_payloads::<Payload3<16>, 16, _>(&mut group, &data, seed);
+  bench_payloads::<Payload3<64>, 64, _>(&mut group, &data, seed);
+  bench_payloads::<Payload16<4>, 4, _>(&mut group, &data, seed);
+  bench_payloads::<Payload16<16>, 16, _>(&mut group, &data, seed);
+  bench_payloads::<Payload16<64>, 64, _>(&mut group, &data, seed);
+  bench_payloads::<Payload64<4>, 4, _>(&mut group, &data, seed);
+  bench_payloads::<Payload64<16>, 16, _>(&mut group, &data, seed);
+  bench_payloads::<Payload64<64>, 64, _>(&mut group, &data, seed);
+  bench_payloads::<Payload256<4>, 4, _>(&mut group, &data, seed);
+  bench_payloads::<Payload256<16>, 16, _>(&mut group, &data, seed);
+  bench_payloads::<Payload256<64>, 64, _>(&mut group, &data, seed);
+  group.finish();
+
+  // Run bytecode programs whose opcodes follow the layouts, where each
+  // element is an instruction, so the throughput is in ops per second.
+  let mut group = counted::Group::new(c, "dispatch interpreter", SIZE as u64);
+  for opcodes in 1..=OPCODES {
+    bench_interpreter(&mut group, opcodes, &lazy_program(Layout::Random, opcodes, seed));
+  }
+  group.finish();
+  let mut group = counted::Group::new(c, "dispatch interpreter layout", SIZE as u64);
+  for layout in [Layout::SortedBlocks, Layout::TickTock, Layout::ShuffledWindow { copies: 10 },
+                 Layout::Markov { stay: 0.9 }, Layout::Random] {
+    bench_interpreter(&mut group, layout, &lazy_program(layout, OPCODES, seed));
+  }
+  group.finish();
+
+  // Cycle through the classes without shuffling, like the C++ TICK_TOCK
+  let mut group = counted::Group::new(c, "dispatch ticktock", SIZE as u64);
+  for number_of_classes in 1..=20 {
+    let ids = lazy_ids(Layout::TickTock, number_of_classes, seed);
+    group.bench("objs", number_of_classes, || objs_from_ids(&ids),
+                |objs| iter_objs(black_box(objs)));
+    group.bench("enum", number_of_classes, || enums_from_ids::<BigEnum>(&ids),
+                |enums| iter_enum(black_box(enums)));
+    group.bench("lambdas", number_of_classes, || &ids[..],
+                |ids| iter_lambdas(black_box(ids), black_box(PROCESSOR_FUNCS)));
+  }
+  group.finish();
+
+  // Try different probabilities of repeating the previous class
+  let mut group = counted::Group::new(c, "dispatch markov", SIZE as u64);
+  for stay in [0.0, 0.5, 0.9, 0.99] {
+    group.bench("objs", stay, || objs_from_layout(Layout::Markov { stay }, 50, seed),
+                |objs| iter_objs(black_box(objs)));
   }
+  group.finish();
 }
@@ -1,28 +1,47 @@
 // Copyright by Owen O'Malley 2024
 
 use criterion::{black_box, Criterion};
+use std::cell::LazyCell;
+use rust_bench::cache;
+use rust_bench::kernels::dual::*;
+use crate::counted;
+use crate::sweep;
 
-// Compare zip and for loops. The functions multiply the values
-// and sum the products.
+// Compare zip and for loops. The kernels are in
+// rust_bench::kernels::dual.
 
-/// Use iteration, zip, map, and sum.
-fn dual_via_zip(left: &[i32], right: &[i32]) -> i32 {
-  left.iter().zip(right.iter())
-      .map(|(l,r)| l * r)
-      .sum()
-}
-
-/// Use the manual for loop.
-fn dual_via_for(left: &[i32], right: &[i32]) -> i32 {
-  let mut result = 0;
-  for i in 0..left.len() {
-    result += left[i] * right[i];
-  }
-  result
-}
+const SIZE: usize = 10_000;
 
 pub fn benchmark(c: &mut Criterion) {
-  let array: [i32; 10_000] = rust_bench::random_array(-100_000..100_000, 0);
-  c.bench_function("dual zip", |b| b.iter(|| dual_via_zip(black_box(&array), black_box(&array))));
-  c.bench_function("dual for", |b| b.iter(|| dual_via_for(black_box(&array), black_box(&array))));
+  let seed = rust_bench::data_seed();
+  let array = LazyCell::new(|| {
+    let array: [i32; SIZE] = rust_bench::random_array(RANGE, seed);
+    check(&array, &array);
+    array
+  });
+  let elements = SIZE as u64;
+  counted::bench_function(c, "dual zip", elements, || &array[..],
+                          |array| dual_via_zip(black_box(array), black_box(array)));
+  counted::bench_function(c, "dual for", elements, || &array[..],
+                          |array| dual_via_for(black_box(array), black_box(array)));
+
+  // Sweep the size of the two vectors across the caches.
+  let sizes = cache::sweep_sizes(sweep::MAX_SIZE);
+  sweep::describe("dual size", &sizes, 2 * size_of::<i32>());
+  let mut group = counted::Group::new(c, "dual size", 0);
+  let range = sweep::product_range(RANGE.end);
+  for size in sizes {
+    let vecs = LazyCell::new(|| {
+      let vecs = (rust_bench::random_vec_in(range.clone(), size, 2 * seed),
+                  rust_bench::random_vec_in(range.clone(), size, 2 * seed + 1));
+      check(&vecs.0, &vecs.1);
+      vecs
+    });
+    sweep::configure(&mut group, size);
+    group.bench("zip", size, || &*vecs,
+                |(left, right)| dual_via_zip(black_box(left), black_box(right)));
+    group.bench("for", size, || &*vecs,
+                |(left, right)| dual_via_for(black_box(left), black_box(right)));
+  }
+  group.finish();
 }
@@ -1,50 +1,60 @@
 // Copyright by Owen O'Malley 2024
 
 use criterion::{black_box, Criterion};
+use std::cell::LazyCell;
+use rust_bench::cache;
+use rust_bench::kernels::elements::*;
+use crate::counted;
+use crate::sweep;
 
 // This group tests the relative performance of loops,
-// iterators, arrays, and vectors.
+// iterators, arrays, and vectors. The kernels are in
+// rust_bench::kernels::elements.
 
-// All of the functions compute the sum of the data.
-
-/// Use a for loop via indexing
-fn sum_via_index(data: &[i32]) -> i32 {
-  let mut sum = 0;
-  for i in 0..data.len() {
-    sum += data[i];
-  }
-  sum
-}
-
-/// A for loop over the data directly
-fn sum_via_for(data: &[i32]) -> i32 {
-  let mut sum = 0;
-  for i in data {
-    sum += i;
-  }
-  sum
-}
-
-/// iterate and use sum.
-fn sum_via_sum(data: &[i32]) -> i32 {
-  data.iter().sum()
-}
-
-/// Iterate and use fold.
-fn sum_via_fold(data: &[i32]) -> i32 {
-  data.iter().fold(0, |acc, i| acc + i)
-}
+const SIZE: usize = 10_000;
 
 pub fn benchmark(c: &mut Criterion) {
-  let array: [i32; 10_000] = rust_bench::random_array(-100_000..100_000, 0);
-  let vec = array.to_vec();
-  c.bench_function("element index array", |b| b.iter(|| sum_via_index(black_box(&array))));
-  c.bench_function("element for array", |b| b.iter(|| sum_via_for(black_box(&array))));
-  c.bench_function("element sum array", |b| b.iter(|| sum_via_sum(black_box(&array))));
-  c.bench_function("element fold array", |b| b.iter(|| sum_via_fold(black_box(&array))));
-
-  c.bench_function("element index vec", |b| b.iter(|| sum_via_index(black_box(&vec))));
-  c.bench_function("element for vec", |b| b.iter(|| sum_via_for(black_box(&vec))));
-  c.bench_function("element sum vec", |b| b.iter(|| sum_via_sum(black_box(&vec))));
-  c.bench_function("element fold vec", |b| b.iter(|| sum_via_fold(black_box(&vec))));
+  let seed = rust_bench::data_seed();
+  let array = LazyCell::new(|| {
+    let array: [i32; SIZE] = rust_bench::random_array(-100_000..100_000, seed);
+    check(&array);
+    array
+  });
+  let elements = SIZE as u64;
+  let vec = LazyCell::new(|| array.to_vec());
+  counted::bench_function(c, "element index array", elements, || &*array,
+                          |array| sum_via_index(black_box(*array)));
+  counted::bench_function(c, "element for array", elements, || &*array,
+                          |array| sum_via_for(black_box(*array)));
+  counted::bench_function(c, "element sum array", elements, || &*array,
+                          |array| sum_via_sum(black_box(*array)));
+  counted::bench_function(c, "element fold array", elements, || &*array,
+                          |array| sum_via_fold(black_box(*array)));
+
+  counted::bench_function(c, "element index vec", elements, || &*vec,
+                          |vec| sum_via_index(black_box(vec)));
+  counted::bench_function(c, "element for vec", elements, || &*vec,
+                          |vec| sum_via_for(black_box(vec)));
+  counted::bench_function(c, "element sum vec", elements, || &*vec,
+                          |vec| sum_via_sum(black_box(vec)));
+  counted::bench_function(c, "element fold vec", elements, || &*vec,
+                          |vec| sum_via_fold(black_box(vec)));
+
+  // Sweep the size of the vector across the caches.
+  let sizes = cache::sweep_sizes(sweep::MAX_SIZE);
+  sweep::describe("element size", &sizes, size_of::<i32>());
+  let mut group = counted::Group::new(c, "element size", 0);
+  for size in sizes {
+    let vec = LazyCell::new(|| {
+      let vec = rust_bench::random_vec_in(sweep::sum_range(100_000), size, seed);
+      check(&vec);
+      vec
+    });
+    sweep::configure(&mut group, size);
+    group.bench("index", size, || &vec[..], |vec| sum_via_index(black_box(vec)));
+    group.bench("for", size, || &vec[..], |vec| sum_via_for(black_box(vec)));
+    group.bench("sum", size, || &vec[..], |vec| sum_via_sum(black_box(vec)));
+    group.bench("fold", size, || &vec[..], |vec| sum_via_fold(black_box(vec)));
+  }
+  group.finish();
 }
@@ -0,0 +1,132 @@
+// Copyright by Owen O'Malley 2024
+
+use std::env;
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use rust_bench::instructions;
+use rust_bench::perf::{CounterSet, Counters};
+use rust_bench::results::BenchResult;
+
+// Count the instructions of the benchmarks instead of timing them when
+// $RUST_BENCH_INSTRUCTIONS is set. See rust_bench::instructions.
+
+/// The number of elements to process in each sample with the perf
+/// counters.
+const PERF_ELEMENTS: u64 = 1_000_000;
+
+/// The number of samples with the perf counters.
+const PERF_SAMPLES: usize = 10;
+
+/// The number of elements to process under callgrind, which is about a
+/// hundred times slower.
+const CALLGRIND_ELEMENTS: u64 = 100_000;
+
+/// A benchmark that callgrind dumped, which is read at the end.
+struct Dumped {
+  id: String,
+  group: String,
+  function: Option<String>,
+  parameter: Option<String>,
+  elements: u64,
+  iterations: u64,
+}
+
+enum Backend {
+  Perf(CounterSet, Vec<BenchResult>),
+  Callgrind(PathBuf, Vec<Dumped>),
+}
+
+static BACKEND: Mutex<Option<Backend>> = Mutex::new(None);
+
+/// Choose the backend if instruction counting is on and return whether
+/// it is.
+pub fn init() -> Result<bool, String> {
+  if env::var_os("RUST_BENCH_INSTRUCTIONS").is_none() {
+    return Ok(false);
+  }
+  let backend = if instructions::running_on_valgrind() {
+    let dir = env::var_os("RUST_BENCH_CALLGRIND_DIR")
+        .ok_or("Under callgrind, RUST_BENCH_CALLGRIND_DIR must name the dump directory")?;
+    Backend::Callgrind(PathBuf::from(dir), Vec::new())
+  } else {
+    let set = CounterSet::open()
+        .map_err(|err| format!("Counting instructions needs perf counters or callgrind: {err}"))?;
+    Backend::Perf(set, Vec::new())
+  };
+  *BACKEND.lock().unwrap() = Some(backend);
+  Ok(true)
+}
+
+pub fn active() -> bool {
+  BACKEND.lock().unwrap().is_some()
+}
+
+/// Count the instructions of f, which processes the given number of
+/// elements.
+pub fn measure<R>(id: &str, group: &str, function: Option<&str>, parameter: Option<&str>,
+                  elements: u64, mut f: impl FnMut() -> R) {
+  let mut backend = BACKEND.lock().unwrap();
+  match backend.as_mut().expect("instruction counting is off") {
+    Backend::Perf(set, results) => {
+      let iterations = (PERF_ELEMENTS / elements.max(1)).max(1);
+      black_box(f());
+      let mut samples: Vec<Counters> = Vec::new();
+      for _ in 0..PERF_SAMPLES {
+        match set.measure(iterations, elements, &mut f) {
+          Ok(counters) if counters.instructions.is_some() => samples.push(counters),
+          Ok(_) => return eprintln!("Can't count the instructions of {id}"),
+          Err(err) => return eprintln!("Can't count {id}: {err}"),
+        }
+      }
+      let per_iteration = |c: &Counters| c.instructions.unwrap() * elements.max(1) as f64;
+      samples.sort_by(|a, b| per_iteration(a).total_cmp(&per_iteration(b)));
+      let median = samples[samples.len() / 2];
+      let result = BenchResult::from_samples(id, group, function, parameter, elements,
+                                             samples.iter().map(per_iteration).collect(),
+                                             Some(median));
+      println!("{id}: {:.0} instructions per iteration", result.estimate);
+      results.push(result);
+    }
+    Backend::Callgrind(_, dumped) => {
+      let iterations = (CALLGRIND_ELEMENTS / elements.max(1)).max(1);
+      // Warm up the simulated caches before the counts are zeroed.
+      instructions::start_instrumentation();
+      black_box(f());
+      instructions::zero_stats();
+      for _ in 0..iterations {
+        black_box(f());
+      }
+      instructions::dump_stats_at(id);
+      instructions::stop_instrumentation();
+      dumped.push(Dumped { id: id.to_string(), group: group.to_string(),
+        function: function.map(str::to_string), parameter: parameter.map(str::to_string),
+        elements, iterations });
+    }
+  }
+}
+
+/// Take the results of the benchmarks that were counted.
+pub fn take() -> Result<Vec<BenchResult>, String> {
+  match BACKEND.lock().unwrap().take() {
+    None => Ok(Vec::new()),
+    Some(Backend::Perf(_, results)) => Ok(results),
+    Some(Backend::Callgrind(dir, dumped)) => {
+      let dumps = instructions::read_dumps(&dir)
+          .map_err(|err| format!("Can't read the dumps in {}: {err}", dir.display()))?;
+      let mut results = Vec::new();
+      for bench in dumped {
+        let Some(events) = dumps.get(&bench.id) else {
+          eprintln!("No callgrind dump for {}", bench.id);
+          continue;
+        };
+        let per_iteration = events.instructions as f64 / bench.iterations as f64;
+        println!("{}: {per_iteration:.0} instructions per iteration", bench.id);
+        results.push(BenchResult::from_samples(&bench.id, &bench.group,
+            bench.function.as_deref(), bench.parameter.as_deref(), bench.elements,
+            vec![per_iteration], Some(events.per_element(bench.iterations, bench.elements))));
+      }
+      Ok(results)
+    }
+  }
+}
@@ -1,74 +1,57 @@
 // Copyright by Owen O'Malley 2024
 
 use criterion::{black_box, Criterion};
+use std::cell::LazyCell;
+use rust_bench::cache;
+use rust_bench::kernels::option::*;
+use crate::counted;
+use crate::sweep;
 
-// Compare the various ways to handle Option values.
+// Compare the various ways to handle Option values. The kernels are in
+// rust_bench::kernels::option.
 
-// These functions take a slice of Option<String> and compute the
-// sum of the lengths of the String values.
+const SIZE: usize = 10_000;
 
-/// Iterate through the data, apply filter_map to drop the None values
-/// and keep the string lengths. Finally sum is applied.
-fn filter_map(data: &[Option<String>]) -> usize {
-  data.iter().filter_map(|x|
-     x.as_ref().and_then(|s| Some(s.len()))).sum()
-}
-
-/// A for loop and if to test whether it is a String.
-fn for_if(data: &[Option<String>]) -> usize {
-  let mut result = 0;
-  for x in data {
-    if x.is_some() {
-      result += x.as_ref().unwrap().len();
-    }
-  }
-  result
-}
-
-/// As above, but with if let to find the Strings.
-fn for_if_let(data: &[Option<String>]) -> usize {
-  let mut result = 0;
-  for x in data {
-    if let Some(s) = x {
-      result += s.len();
-    }
-  }
-  result
-}
-
-/// A for loop with match to find strings.
-fn for_match(data: &[Option<String>]) -> usize {
-  let mut result = 0;
-  for x in data {
-    match x {
-      Some(s) => result += s.len(),
-      _ => {},
-    }
+pub fn benchmark(c: &mut Criterion) {
+  let seed = rust_bench::data_seed();
+  let array = LazyCell::new(|| {
+    let array: [Option<String>; SIZE] = rust_bench::random_string_array(seed);
+    check(&array);
+    array
+  });
+  let elements = SIZE as u64;
+  counted::bench_function(c, "option for if", elements, || &array[..],
+                          |array| for_if(black_box(array)));
+  counted::bench_function(c, "option for if_let", elements, || &array[..],
+                          |array| for_if_let(black_box(array)));
+  counted::bench_function(c, "option for match", elements, || &array[..],
+                          |array| for_match(black_box(array)));
+  counted::bench_function(c, "option filer_map", elements, || &array[..],
+                          |array| filter_map(black_box(array)));
+  counted::bench_function(c, "option match sum", elements, || &array[..],
+                          |array| match_sum(black_box(array)));
+  counted::bench_function(c, "option map_or", elements, || &array[..],
+                          |array| map_or(black_box(array)));
+
+  // Sweep the size of the vector across the caches. The strings are
+  // allocated separately, so this stops at a smaller size than the
+  // other sweeps.
+  let sizes = cache::sweep_sizes(sweep::MAX_SIZE / 16);
+  sweep::describe("option size", &sizes, size_of::<Option<String>>());
+  let mut group = counted::Group::new(c, "option size", 0);
+  for size in sizes {
+    let vec = LazyCell::new(|| {
+      let vec = rust_bench::random_string_vec(size, seed);
+      check(&vec);
+      vec
+    });
+    sweep::configure(&mut group, size);
+    group.bench("for if", size, || &vec[..], |vec| for_if(black_box(vec)));
+    group.bench("for if_let", size, || &vec[..], |vec| for_if_let(black_box(vec)));
+    group.bench("for match", size, || &vec[..], |vec| for_match(black_box(vec)));
+    group.bench("filter_map", size, || &vec[..], |vec| filter_map(black_box(vec)));
+    group.bench("match sum", size, || &vec[..], |vec| match_sum(black_box(vec)));
+    group.bench("map_or", size, || &vec[..], |vec| map_or(black_box(vec)));
   }
-  result
-}
-
-/// Iteration, match to find the Strings, and sum.
-fn match_sum(data: &[Option<String>]) -> usize {
-  data.iter().map(|x| match x {
-    Some(s) => s.len(),
-    _ => 0,
-  }).sum()
+  group.finish();
 }
-
-/// Uses Option.map_or, which provides a default value if the input is None
-/// or applies a lambda when it has a value.
-fn map_or(data: &[Option<String>]) -> usize {
-  data.iter().map(|x| 
-    x.as_ref().map_or(0, |s| s.len())).sum()
-}
-
-pub fn benchmark(c: &mut Criterion) {
-  let array: [Option<String>; 10_000] = rust_bench::random_string_array(0);
-  c.bench_function("option for if", |b| b.iter(|| for_if(black_box(&array))));
-  c.bench_function("option for if_let", |b| b.iter(|| for_if_let(black_box(&array))));
-  c.bench_function("option for match", |b| b.iter(|| for_match(black_box(&array))));
-  c.bench_function("option filer_map", |b| b.iter(|| filter_map(black_box(&array))));
-  c.bench_function("option match sum", |b| b.iter(|| match_sum(black_box(&array))));
-  c.bench_function("option map_or", |b| b.iter(|| map_or(black_box(&array))));
-}
\ No newline at end of file
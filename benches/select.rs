@@ -0,0 +1,72 @@
+// Copyright by Owen O'Malley 2024
+
+use std::sync::Mutex;
+use rust_bench::select::{Bench, Selection};
+
+// Track the category that is running and decide which of its benchmarks
+// to run or list. See rust_bench::select for the environment variables.
+
+struct State {
+  selection: Selection,
+  category: &'static str,
+  /// The selected benchmarks as category, group, variant, and parameter.
+  listed: Vec<[String; 4]>,
+}
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+/// Set the selection for the run.
+pub fn init(selection: Selection) {
+  *STATE.lock().unwrap() = Some(State { selection, category: "", listed: Vec::new() });
+}
+
+/// Start the benchmarks of a category and return whether any of them
+/// may be selected.
+pub fn start_category(category: &'static str) -> bool {
+  let mut state = STATE.lock().unwrap();
+  let state = state.as_mut().expect("selection isn't initialized");
+  state.category = category;
+  state.selection.wants_category(category)
+}
+
+/// Whether to run the benchmark. When listing, it records the benchmark
+/// and returns false.
+pub fn wants(group: &str, variant: Option<&str>, parameter: Option<&str>) -> bool {
+  let mut state = STATE.lock().unwrap();
+  let state = state.as_mut().expect("selection isn't initialized");
+  let bench = Bench { category: state.category, group, variant, parameter };
+  if !state.selection.wants(&bench) {
+    return false;
+  }
+  if state.selection.list {
+    state.listed.push([bench.category, group, variant.unwrap_or(""), parameter.unwrap_or("")]
+        .map(str::to_string));
+    return false;
+  }
+  true
+}
+
+/// Whether the run only lists the benchmarks.
+pub fn listing() -> bool {
+  let state = STATE.lock().unwrap();
+  state.as_ref().expect("selection isn't initialized").selection.list
+}
+
+/// Print the listed benchmarks as a table.
+pub fn print_list() {
+  let state = STATE.lock().unwrap();
+  let listed = &state.as_ref().expect("selection isn't initialized").listed;
+  let headers = ["category", "group", "variant", "parameter"];
+  let widths: Vec<usize> = (0..4).map(|i| listed.iter().map(|row| row[i].chars().count())
+      .chain([headers[i].len()]).max().unwrap()).collect();
+  let print_row = |row: [&str; 4]| {
+    let columns: Vec<String> = row.iter().zip(&widths)
+        .map(|(text, width)| format!("{text:<width$}")).collect();
+    println!("{}", columns.join("  ").trim_end());
+  };
+  print_row(headers);
+  for row in listed {
+    print_row([&row[0], &row[1], &row[2], &row[3]].map(String::as_str));
+  }
+  println!("{} benchmarks", listed.len());
+}
@@ -1,26 +1,43 @@
 // Copyright by Owen O'Malley 2024
 
 use criterion::{black_box, Criterion};
+use std::cell::LazyCell;
+use rust_bench::cache;
+use rust_bench::kernels::sliding::*;
+use crate::counted;
+use crate::sweep;
 
-// These functions use a sliding window and compute the 
-// diference between adjacent elements and then sum the differences.
+// Compare the sliding window with the equivalent for loop. The kernels are in
+// rust_bench::kernels::sliding.
 
-/// Use high level window function, map, and sum.
-fn compute_window(data: &[i32]) -> i32 {
-  data.windows(2).map(|x| x[0] - x[1]).sum()
-}
-
-/// Use the equivalent for loop.
-fn compute_for(data: &[i32]) -> i32 {
-  let mut result = 0;
-  for i in 0..(data.len() -1) {
-    result += data[i] - data[i+1];
-  }
-  result
-}
+const SIZE: usize = 10_000;
 
 pub fn benchmark(c: &mut Criterion) {
-  let array: [i32; 10_000] = rust_bench::random_array(-100_000..100_000, 0);
-  c.bench_function("sliding window", |b| b.iter(|| compute_window(black_box(&array))));
-  c.bench_function("sliding for", |b| b.iter(|| compute_for(black_box(&array))));
+  let seed = rust_bench::data_seed();
+  let array = LazyCell::new(|| {
+    let array: [i32; SIZE] = rust_bench::random_array(-100_000..100_000, seed);
+    check(&array);
+    array
+  });
+  let elements = SIZE as u64;
+  counted::bench_function(c, "sliding window", elements, || &array[..],
+                          |array| compute_window(black_box(array)));
+  counted::bench_function(c, "sliding for", elements, || &array[..],
+                          |array| compute_for(black_box(array)));
+
+  // Sweep the size of the vector across the caches.
+  let sizes = cache::sweep_sizes(sweep::MAX_SIZE);
+  sweep::describe("sliding size", &sizes, size_of::<i32>());
+  let mut group = counted::Group::new(c, "sliding size", 0);
+  for size in sizes {
+    let vec = LazyCell::new(|| {
+      let vec = rust_bench::random_vec_in(-100_000..100_000, size, seed);
+      check(&vec);
+      vec
+    });
+    sweep::configure(&mut group, size);
+    group.bench("window", size, || &vec[..], |vec| compute_window(black_box(vec)));
+    group.bench("for", size, || &vec[..], |vec| compute_for(black_box(vec)));
+  }
+  group.finish();
 }
@@ -0,0 +1,42 @@
+// Copyright by Owen O'Malley 2024
+
+use std::ops::Range;
+use rust_bench::cache;
+use crate::counted;
+use crate::select;
+
+// Helpers for the sweeps over the size of the input.
+
+/// The largest number of elements in the sweeps, which is far larger
+/// than the last level cache.
+pub const MAX_SIZE: usize = 64 << 20;
+
+/// A symmetric range no wider than limit in which the sum of MAX_SIZE
+/// values can't overflow an i32. Every size in a sweep uses the same
+/// range, so that the data doesn't change along with the size.
+pub fn sum_range(limit: i32) -> Range<i32> {
+  let bound = (i32::MAX as usize / MAX_SIZE).min(limit as usize) as i32;
+  -bound..bound.max(1)
+}
+
+/// A symmetric range no wider than limit in which the sum of the
+/// products of MAX_SIZE pairs of values can't overflow an i32.
+pub fn product_range(limit: i32) -> Range<i32> {
+  let bound = ((i32::MAX as usize / MAX_SIZE) as f64).sqrt() as i32;
+  -bound.min(limit)..bound.min(limit).max(1)
+}
+
+/// Set the throughput for the size and take fewer samples of the large
+/// sizes, which take tens of milliseconds per iteration.
+pub fn configure(group: &mut counted::Group, size: usize) {
+  group.set_elements(size as u64);
+  group.sample_size(if size >= 1 << 22 { 10 } else { 100 });
+}
+
+/// Describe where the sizes of the sweep fit in the caches, unless the
+/// run only lists the benchmarks.
+pub fn describe(name: &str, sizes: &[usize], element_bytes: usize) {
+  if !select::listing() {
+    cache::describe_sweep(name, sizes, element_bytes);
+  }
+}
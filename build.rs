@@ -0,0 +1,31 @@
+// Copyright by Owen O'Malley 2024
+
+use std::env;
+use std::process::Command;
+
+// Compile the C++ comparison kernels with the system C++ compiler and
+// record the toolchain for the machine fingerprint.
+
+fn main() {
+  println!("cargo:rerun-if-changed=c++/kernels.cc");
+  println!("cargo:rerun-if-changed=c++/processors.h");
+  cc::Build::new()
+      .cpp(true)
+      .std("c++20")
+      .file("c++/kernels.cc")
+      .compile("cpp_kernels");
+
+  let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+  let version = Command::new(rustc).arg("-V").output().ok()
+      .and_then(|out| String::from_utf8(out.stdout).ok())
+      .unwrap_or_default();
+  println!("cargo:rustc-env=RUST_BENCH_RUSTC={}", version.trim());
+  println!("cargo:rustc-env=RUST_BENCH_TARGET={}", env::var("TARGET").unwrap_or_default());
+  println!("cargo:rustc-env=RUST_BENCH_TARGET_FEATURES={}",
+           env::var("CARGO_CFG_TARGET_FEATURE").unwrap_or_default());
+  println!("cargo:rustc-env=RUST_BENCH_PROFILE={}", env::var("PROFILE").unwrap_or_default());
+  println!("cargo:rustc-env=RUST_BENCH_OPT_LEVEL={}", env::var("OPT_LEVEL").unwrap_or_default());
+  println!("cargo:rustc-env=RUST_BENCH_RUSTFLAGS={}",
+           env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default());
+  println!("cargo:rustc-env=RUST_BENCH_PANIC={}", env::var("CARGO_CFG_PANIC").unwrap_or_default());
+}
@@ -0,0 +1,95 @@
+// The C++ kernels that the Rust benchmarks call through FFI, so that
+// both languages are measured by Criterion over the same input arrays.
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <numeric>
+#include <span>
+#include <vector>
+
+#include "processors.h"
+
+struct cpp_processors {
+  std::vector<std::unique_ptr<Processor>> objs;
+};
+
+extern "C" {
+
+// Create a Processor object for each class id.
+cpp_processors* cpp_processors_new(const int32_t* ids, size_t len) {
+  cpp_processors* result = new cpp_processors();
+  result->objs.reserve(len);
+  for (size_t i = 0; i < len; ++i) {
+    result->objs.push_back(create(ids[i]));
+  }
+  return result;
+}
+
+void cpp_processors_free(cpp_processors* data) {
+  delete data;
+}
+
+// Use virtual dispatch through the Processor class.
+int32_t cpp_iter_objs(const cpp_processors* data) {
+  int32_t result = 0;
+  for (const std::unique_ptr<Processor>& item: data->objs) {
+    result += item->process();
+  }
+  return result;
+}
+
+// Use a switch on the class id, which is the equivalent of a Rust enum.
+int32_t cpp_switch_dispatch(const int32_t* ids, size_t len) {
+  int32_t result = 0;
+  for (size_t i = 0; i < len; ++i) {
+    switch (ids[i]) {
+    case 0: result += 1; break;
+    case 1: result += 2; break;
+    case 2: result += 3; break;
+    case 3: result += 5; break;
+    case 4: result += 7; break;
+    case 5: result += 11; break;
+    case 6: result += 13; break;
+    case 7: result += 17; break;
+    case 8: result += 19; break;
+    case 9: result += 23; break;
+    case 10: result += 25; break;
+    case 11: result += 27; break;
+    case 12: result += 29; break;
+    case 13: result += 31; break;
+    case 14: result += 33; break;
+    case 15: result += 35; break;
+    case 16: result += 37; break;
+    case 17: result += 39; break;
+    case 18: result += 41; break;
+    case 19: result += 43; break;
+    }
+  }
+  return result;
+}
+
+// Sum via a for loop with an index.
+int32_t cpp_sum_via_index(const int32_t* data, size_t len) {
+  int32_t sum = 0;
+  for (size_t i = 0; i < len; ++i) {
+    sum += data[i];
+  }
+  return sum;
+}
+
+// Sum via a range based for loop.
+int32_t cpp_sum_via_for(const int32_t* data, size_t len) {
+  int32_t sum = 0;
+  for (int32_t val: std::span(data, len)) {
+    sum += val;
+  }
+  return sum;
+}
+
+// Sum via std::accumulate.
+int32_t cpp_sum_via_accumulate(const int32_t* data, size_t len) {
+  return std::accumulate(data, data + len, int32_t(0));
+}
+
+}
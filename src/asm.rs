@@ -0,0 +1,253 @@
+// Copyright by Owen O'Malley 2024
+
+// Read the machine code of the kernels out of a built binary, so that
+// findings such as a kernel not being tail recursive can be checked in
+// the listing rather than guessed from the timings. The disassembly
+// comes from objdump, and each kernel is summarized with its number of
+// instructions, its loops, whether it uses packed SIMD instructions, and
+// the functions that it calls.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// The prefix of the demangled names of the kernels.
+pub const KERNEL_PREFIX: &str = "rust_bench::kernels::";
+
+/// The sources of the kernel modules, which list the kernels that
+/// should be in the binary.
+const SOURCES: &[(&str, &str)] = &[
+  ("branching", include_str!("kernels/branching.rs")),
+  ("dispatch", include_str!("kernels/dispatch.rs")),
+  ("dual", include_str!("kernels/dual.rs")),
+  ("elements", include_str!("kernels/elements.rs")),
+  ("interpreter", include_str!("kernels/interpreter.rs")),
+  ("option", include_str!("kernels/option.rs")),
+  ("sliding", include_str!("kernels/sliding.rs")),
+  ("tail", include_str!("kernels/tail.rs")),
+];
+
+/// The names of the public functions in the kernel modules, such as
+/// "tail::sum_via_match", other than the checks.
+pub fn kernel_names() -> Vec<String> {
+  let mut result = Vec::new();
+  for (module, source) in SOURCES {
+    for line in source.lines() {
+      let Some(rest) = line.strip_prefix("pub fn ") else {
+        continue;
+      };
+      let name: String = rest.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+      if !name.starts_with("check") && name != "verify" {
+        result.push(format!("{module}::{name}"));
+      }
+    }
+  }
+  result
+}
+
+/// One disassembled instruction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instruction {
+  pub address: u64,
+  pub mnemonic: String,
+  pub operands: String,
+}
+
+/// The disassembly of one function.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+  /// The demangled name without the hash.
+  pub name: String,
+  pub address: u64,
+  pub instructions: Vec<Instruction>,
+}
+
+/// The prefixes that objdump prints before the mnemonic.
+const PREFIXES: &[&str] = &["lock", "rep", "repz", "repe", "repnz", "repne", "bnd", "notrack",
+  "data16", "cs", "ds"];
+
+fn parse_instruction(line: &str) -> Option<Instruction> {
+  let (address, rest) = line.trim_start().split_once(':')?;
+  let address = u64::from_str_radix(address, 16).ok()?;
+  let mut rest = rest.trim();
+  let mut mnemonic = "";
+  while !rest.is_empty() {
+    let (word, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    mnemonic = word;
+    rest = tail.trim_start();
+    if !PREFIXES.contains(&word) {
+      break;
+    }
+  }
+  (!mnemonic.is_empty()).then(|| Instruction { address, mnemonic: mnemonic.to_string(),
+    operands: rest.to_string() })
+}
+
+/// Parse the output of objdump -d -C into its functions.
+pub fn parse_objdump(text: &str) -> Vec<Function> {
+  let mut result: Vec<Function> = Vec::new();
+  let mut current: Option<Function> = None;
+  for line in text.lines() {
+    // A function starts with a line like "0000000000204ce0 <name>:".
+    if let Some((address, name)) = line.strip_suffix(">:").and_then(|l| l.split_once(" <")) {
+      if let Ok(address) = u64::from_str_radix(address, 16) {
+        result.extend(current.take());
+        current = Some(Function { name: name.to_string(), address, instructions: Vec::new() });
+        continue;
+      }
+    }
+    if let (Some(function), Some(instruction)) = (current.as_mut(), parse_instruction(line)) {
+      function.instructions.push(instruction);
+    }
+  }
+  result.extend(current);
+  result
+}
+
+/// Parse the output of objdump -R -C into the targets of the slots in
+/// the global offset table. A position independent binary calls its own
+/// functions through these slots, which objdump only names relative to
+/// _DYNAMIC.
+pub fn parse_relocations(text: &str, functions: &[Function]) -> HashMap<u64, String> {
+  let names: HashMap<u64, &str> = functions.iter().map(|f| (f.address, f.name.as_str())).collect();
+  let mut result = HashMap::new();
+  for line in text.lines() {
+    let mut words = line.split_whitespace();
+    let (Some(offset), Some(_), Some(value)) = (words.next(), words.next(), words.next()) else {
+      continue;
+    };
+    let Ok(offset) = u64::from_str_radix(offset, 16) else {
+      continue;
+    };
+    let name = match value.strip_prefix("*ABS*+0x") {
+      Some(address) => u64::from_str_radix(address, 16).ok()
+          .and_then(|a| names.get(&a)).map(|n| n.to_string()),
+      None => Some(value.split_once('@').map_or(value, |(name, _)| name).to_string()),
+    };
+    if let Some(name) = name {
+      result.insert(offset, name);
+    }
+  }
+  result
+}
+
+/// Name the targets of the indirect calls and jumps through the slots.
+pub fn resolve_slots(functions: &mut [Function], slots: &HashMap<u64, String>) {
+  for instruction in functions.iter_mut().flat_map(|f| f.instructions.iter_mut()) {
+    let Some((operands, comment)) = instruction.operands.split_once(" # ") else {
+      continue;
+    };
+    let slot = comment.split_whitespace().next().and_then(|s| u64::from_str_radix(s, 16).ok());
+    if let Some(name) = slot.and_then(|s| slots.get(&s)) {
+      instruction.operands = format!("{} # <{name}>", operands.trim_end());
+    }
+  }
+}
+
+/// The summary of a function's machine code.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Analysis {
+  pub instructions: usize,
+  /// The number of jumps back to an earlier address in the function.
+  pub loops: usize,
+  /// Whether the function uses packed SIMD instructions.
+  pub vectorized: bool,
+  /// The functions that it calls or jumps to, such as tail calls.
+  pub calls: Vec<String>,
+  /// Whether the function calls itself rather than looping.
+  pub recursive: bool,
+}
+
+/// The target of a call or jump, such as "20f290 <name+0x10>" or
+/// "QWORD PTR [rip+0x1f05c3] # <name>".
+fn target(operands: &str) -> (Option<u64>, Option<&str>) {
+  let address = operands.split_whitespace().next().and_then(|a| u64::from_str_radix(a, 16).ok());
+  let named = operands.rsplit_once(" # ").map_or(operands, |(_, comment)| comment);
+  let name = named.find('<').zip(named.rfind('>')).filter(|(start, end)| start < end)
+      .map(|(start, end)| &named[start + 1..end])
+      .map(|name| name.rsplit_once("+0x").map_or(name, |(name, _)| name));
+  (address, name)
+}
+
+/// Whether an instruction works on packed vectors of values.
+fn is_packed(instruction: &Instruction) -> bool {
+  let mnemonic = instruction.mnemonic.as_str();
+  let operands = instruction.operands.as_str();
+  // The NEON arrangements on aarch64.
+  if [".16b", ".8h", ".4s", ".2d", ".8b", ".4h", ".2s"].iter().any(|a| operands.contains(a)) {
+    return true;
+  }
+  if operands.contains("ymm") || operands.contains("zmm") {
+    return true;
+  }
+  let xmm = operands.contains("xmm");
+  let base = mnemonic.strip_prefix('v').unwrap_or(mnemonic);
+  let packed_float = ["add", "sub", "mul", "div", "min", "max", "fmadd"].iter()
+      .any(|op| base.starts_with(op)) && (base.ends_with("ps") || base.ends_with("pd"));
+  xmm && ((base.starts_with('p') && !base.starts_with("pop") && !base.starts_with("push"))
+      || packed_float)
+}
+
+/// Summarize the machine code of a function.
+pub fn analyze(function: &Function) -> Analysis {
+  let end = function.instructions.last().map_or(function.address, |i| i.address);
+  let mut result = Analysis { instructions: function.instructions.len(), ..Default::default() };
+  for instruction in &function.instructions {
+    result.vectorized |= is_packed(instruction);
+    let is_call = instruction.mnemonic.starts_with("call") || instruction.mnemonic == "bl";
+    let is_jump = instruction.mnemonic.starts_with('j') || instruction.mnemonic == "b"
+        || instruction.mnemonic.starts_with("b.");
+    if !is_call && !is_jump {
+      continue;
+    }
+    let (address, name) = target(&instruction.operands);
+    let inside = address.is_some_and(|a| a >= function.address && a <= end);
+    if is_jump && inside {
+      if address.is_some_and(|a| a <= instruction.address) {
+        result.loops += 1;
+      }
+    } else if let Some(name) = name {
+      if is_call && name == function.name {
+        result.recursive = true;
+      }
+      if !result.calls.iter().any(|c| c == name) {
+        result.calls.push(name.to_string());
+      }
+    }
+  }
+  result
+}
+
+/// Format a function as a listing with its analysis at the top.
+pub fn listing(function: &Function, analysis: &Analysis) -> String {
+  let mut result = String::new();
+  let _ = writeln!(result, "; {} at {:#x}", function.name, function.address);
+  let _ = writeln!(result, "; {} instructions, {} loops, {}vectorized{}", analysis.instructions,
+                   analysis.loops, if analysis.vectorized { "" } else { "not " },
+                   if analysis.recursive { ", recursive" } else { "" });
+  for call in &analysis.calls {
+    let _ = writeln!(result, "; calls {call}");
+  }
+  for instruction in &function.instructions {
+    let _ = writeln!(result, "{:8x}:  {:<8} {}", instruction.address, instruction.mnemonic,
+                     instruction.operands);
+  }
+  result
+}
+
+/// Find the kernels in the functions by their short names, such as
+/// "tail::sum_via_match". Generic kernels can have several copies, and
+/// the kernels that were inlined everywhere have none.
+pub fn kernels(functions: &[Function]) -> BTreeMap<String, Vec<&Function>> {
+  let mut result: BTreeMap<String, Vec<&Function>> = BTreeMap::new();
+  for name in kernel_names() {
+    result.insert(name, Vec::new());
+  }
+  for function in functions {
+    if let Some(short) = function.name.strip_prefix(KERNEL_PREFIX) {
+      if let Some(copies) = result.get_mut(short) {
+        copies.push(function);
+      }
+    }
+  }
+  result
+}
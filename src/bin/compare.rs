@@ -0,0 +1,82 @@
+// Copyright by Owen O'Malley 2024
+
+// Compare two saved runs and exit with a failure if any benchmark
+// regressed significantly.
+//
+//   cargo run --bin compare -- BASE [NEW] [--threshold PERCENT] [--alpha P]
+//
+// BASE and NEW are run directories or results.json files and NEW
+// defaults to the latest run. Changes of less than the threshold,
+// which defaults to 2%, or with a p-value of at least alpha, which
+// defaults to 0.05, are treated as noise.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use rust_bench::compare;
+use rust_bench::report::format_value;
+use rust_bench::results::{self, Run};
+
+fn read_run(path: &Path) -> Result<Run, String> {
+  Run::read(path).map_err(|err| format!("Can't read {}: {err}", path.display()))
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<f64, String> {
+  value.and_then(|v| v.parse().ok()).ok_or_else(|| format!("{flag} needs a number"))
+}
+
+fn run() -> Result<bool, String> {
+  let mut paths = Vec::new();
+  let mut threshold = 2.0;
+  let mut alpha = 0.05;
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--threshold" => threshold = parse_number("--threshold", args.next())?,
+      "--alpha" => alpha = parse_number("--alpha", args.next())?,
+      _ => paths.push(PathBuf::from(arg)),
+    }
+  }
+  let (base, new) = match paths.as_slice() {
+    [base] => (read_run(base)?,
+               read_run(&results::latest_run().map_err(|err| format!("No latest run: {err}"))?)?),
+    [base, new] => (read_run(base)?, read_run(new)?),
+    _ => return Err("Usage: compare BASE [NEW] [--threshold PERCENT] [--alpha P]".to_string()),
+  };
+  if base.measurement != new.measurement {
+    return Err(format!("Can't compare {} with {}", base.measurement, new.measurement));
+  }
+  println!("Base: {} on {}", base.run_id, base.machine.summary());
+  println!("New:  {} on {}", new.run_id, new.machine.summary());
+  let comparison = compare::compare(&base, &new, threshold / 100.0, alpha);
+  let width = comparison.changes.iter().map(|c| c.id.len()).max().unwrap_or(0);
+  println!();
+  println!("{:<width$}  {:>10}  {:>10}  {:>8}  {:>7}", "benchmark", "base", "new", "change", "p");
+  let format = |value| format_value(new.measurement, value);
+  for change in &comparison.changes {
+    println!("{:<width$}  {:>10}  {:>10}  {:>+7.2}%  {:>7.4}  {}", change.id,
+             format(change.base), format(change.new), change.change * 100.0,
+             change.p_value, change.verdict);
+  }
+  for id in &comparison.removed {
+    println!("{id}: only in the base run");
+  }
+  for id in &comparison.added {
+    println!("{id}: only in the new run");
+  }
+  let regressions = comparison.regressions().count();
+  println!();
+  println!("{regressions} of {} benchmarks regressed by more than {threshold}% with p < {alpha}",
+           comparison.changes.len());
+  Ok(regressions == 0)
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(true) => ExitCode::SUCCESS,
+    Ok(false) => ExitCode::FAILURE,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::from(2)
+    }
+  }
+}
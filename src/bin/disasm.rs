@@ -0,0 +1,98 @@
+// Copyright by Owen O'Malley 2024
+
+// Disassemble each kernel from the optimized bench binary into its own
+// listing and print a summary of each one.
+//
+//   cargo run --bin disasm -- [--binary PATH] [--out DIR] [PATTERN...]
+//
+// Without --binary, it builds the bench binary with cargo bench --no-run.
+// The listings go in DIR, which defaults to target/rust-bench/asm, and
+// only the kernels whose names contain one of the patterns are listed.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+use rust_bench::asm;
+
+fn objdump(binary: &PathBuf, args: &[&str]) -> Result<String, String> {
+  let output = Command::new("objdump").args(args).arg(binary).output()
+      .map_err(|err| format!("Can't run objdump: {err}"))?;
+  if !output.status.success() {
+    return Err(format!("objdump failed: {}", String::from_utf8_lossy(&output.stderr)));
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn run() -> Result<(), String> {
+  let mut binary = None;
+  let mut out = results_dir();
+  let mut patterns = Vec::new();
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--binary" => binary = Some(PathBuf::from(args.next().ok_or("--binary needs a path")?)),
+      "--out" => out = PathBuf::from(args.next().ok_or("--out needs a directory")?),
+      _ => patterns.push(arg),
+    }
+  }
+  let binary = match binary {
+    Some(binary) => binary,
+    None => rust_bench::bench_binary()?,
+  };
+  println!("Disassembling {}", binary.display());
+  let mut args = vec!["-d", "-C", "--no-show-raw-insn"];
+  if cfg!(target_arch = "x86_64") {
+    args.extend(["-M", "intel"]);
+  }
+  let mut functions = asm::parse_objdump(&objdump(&binary, &args)?);
+  // Static binaries don't have dynamic relocations, so objdump fails.
+  let relocations = objdump(&binary, &["-R", "-C"]).unwrap_or_default();
+  let slots = asm::parse_relocations(&relocations, &functions);
+  asm::resolve_slots(&mut functions, &slots);
+  let kernels = asm::kernels(&functions);
+  let width = kernels.keys().map(|k| k.len()).max().unwrap_or(0);
+  println!("{:<width$}  {:>6}  {:>5}  {:<10}  calls", "kernel", "instrs", "loops", "vectorized");
+  for (name, copies) in &kernels {
+    if !patterns.is_empty() && !patterns.iter().any(|p| name.contains(p.as_str())) {
+      continue;
+    }
+    if copies.is_empty() {
+      println!("{name:<width$}  inlined into its callers");
+      continue;
+    }
+    for (i, function) in copies.iter().enumerate() {
+      let analysis = asm::analyze(function);
+      let mut path = out.join(name.replace("::", "/"));
+      if copies.len() > 1 {
+        path.as_mut_os_string().push(format!(".{}", i + 1));
+      }
+      path.as_mut_os_string().push(".s");
+      fs::create_dir_all(path.parent().unwrap())
+          .and_then(|_| fs::write(&path, asm::listing(function, &analysis)))
+          .map_err(|err| format!("Can't write {}: {err}", path.display()))?;
+      let calls: Vec<&str> = analysis.calls.iter()
+          .map(|c| c.strip_prefix(asm::KERNEL_PREFIX).unwrap_or(c)).collect();
+      println!("{name:<width$}  {:>6}  {:>5}  {:<10}  {}{}", analysis.instructions, analysis.loops,
+               if analysis.vectorized { "yes" } else { "no" },
+               if analysis.recursive { "recursive: " } else { "" }, calls.join(", "));
+    }
+  }
+  println!("Wrote the listings to {}", out.display());
+  Ok(())
+}
+
+/// The default directory for the listings, next to the saved runs.
+fn results_dir() -> PathBuf {
+  rust_bench::results::runs_dir().parent().unwrap().join("asm")
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,41 @@
+// Copyright by Owen O'Malley 2024
+
+// Export every benchmark in Criterion's directory as a new run. The
+// bench binary does this automatically for the benchmarks it ran, so
+// this is for results that came from elsewhere, such as cargo-criterion.
+// The machine and the start of the run come from the machine.json that
+// the bench binary wrote when it started, since this binary's own build
+// says nothing about the benchmarks'.
+
+use std::fs;
+use std::process::ExitCode;
+use rust_bench::machine::{self, Fingerprint};
+use rust_bench::results::{self, Measurement, Run};
+
+fn run() -> Result<(), String> {
+  let home = rust_bench::criterion_home();
+  let path = machine::recorded_path(&home);
+  let fingerprint = Fingerprint::read(&path).map_err(|err| {
+    format!("Can't read {}, which the bench binary writes when it runs: {err}", path.display())
+  })?;
+  let started = fs::metadata(&path).and_then(|m| m.modified())
+      .map_err(|err| format!("Can't read the time of {}: {err}", path.display()))?;
+  let benchmarks = results::collect(&home, None)
+      .map_err(|err| format!("Can't read {}: {err}", home.display()))?;
+  let seed = rust_bench::seed_from_env()?;
+  let dir = Run::new(started, results::config_from_env(), seed, fingerprint,
+                     Measurement::WallTime, benchmarks).save()
+      .map_err(|err| format!("Can't save results: {err}"))?;
+  println!("Saved results in {}", dir.display());
+  Ok(())
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::FAILURE
+    }
+  }
+}
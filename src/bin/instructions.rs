@@ -0,0 +1,75 @@
+// Copyright by Owen O'Malley 2024
+
+// Count the instructions of the benchmarks instead of timing them.
+//
+//   cargo run --bin instructions -- [--perf] [--binary PATH]
+//
+// When valgrind is installed, it runs the bench binary under callgrind,
+// which counts the instructions and simulates the caches exactly.
+// Otherwise, or with --perf, it counts the user mode instructions with
+// the perf counters. Either way, the counts are saved as a run that the
+// compare tool reads like a timed run. The selection variables, such as
+// RUST_BENCH_CATEGORY, pick the benchmarks.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, ExitCode, Stdio};
+
+fn has_valgrind() -> bool {
+  Command::new("valgrind").arg("--version").stdout(Stdio::null()).stderr(Stdio::null())
+      .status().is_ok_and(|status| status.success())
+}
+
+fn run() -> Result<bool, String> {
+  let mut perf = false;
+  let mut binary = None;
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--perf" => perf = true,
+      "--binary" => binary = Some(PathBuf::from(args.next().ok_or("--binary needs a path")?)),
+      _ => return Err(format!("Unknown argument {arg}")),
+    }
+  }
+  if !perf && !has_valgrind() {
+    println!("Valgrind isn't installed, so counting with the perf counters");
+    perf = true;
+  }
+  let binary = match binary {
+    Some(binary) => binary,
+    None => rust_bench::bench_binary()?,
+  };
+  let mut command = if perf {
+    Command::new(&binary)
+  } else {
+    let dir = rust_bench::results::runs_dir().parent().unwrap().join("callgrind");
+    // Remove the old dumps, since the run reads every file in the directory.
+    if dir.exists() {
+      fs::remove_dir_all(&dir).map_err(|err| format!("Can't clear {}: {err}", dir.display()))?;
+    }
+    fs::create_dir_all(&dir).map_err(|err| format!("Can't create {}: {err}", dir.display()))?;
+    let mut command = Command::new("valgrind");
+    command.args(["--tool=callgrind", "--cache-sim=yes", "--instr-atstart=no"])
+        .arg(format!("--callgrind-out-file={}", dir.join("callgrind.out").display()))
+        .arg(&binary)
+        .env("RUST_BENCH_CALLGRIND_DIR", &dir);
+    command
+  };
+  let status = command.arg("--bench")
+      .env("RUST_BENCH_INSTRUCTIONS", "1")
+      .status()
+      .map_err(|err| format!("Can't run {}: {err}", binary.display()))?;
+  Ok(status.success())
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(true) => ExitCode::SUCCESS,
+    Ok(false) => ExitCode::FAILURE,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::FAILURE
+    }
+  }
+}
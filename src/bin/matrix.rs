@@ -0,0 +1,166 @@
+// Copyright by Owen O'Malley 2024
+
+// Run the benchmarks under a set of compiler configurations, so that we
+// can see which findings depend on the flags rather than the code. It
+// isn't a full matrix of the settings, since the configurations change
+// one setting at a time from the default.
+//
+//   cargo run --bin matrix -- [--configs NAME,...] [--list] [-- CRITERION ARGS...]
+//
+// Each configuration builds in its own directory under target/matrix,
+// so the builds and Criterion's baselines don't mix, and its run is
+// tagged with the configuration's name. The configurations change one
+// setting at a time from the default bench profile, except for max,
+// which combines them.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+use rust_bench::report::format_time;
+use rust_bench::results::{self, Run};
+
+struct Config {
+  name: &'static str,
+  /// The cargo profile, which sets opt-level, lto, and codegen-units.
+  profile: &'static str,
+  /// The flags that profiles can't set for benchmarks. Cargo ignores the
+  /// panic setting of the profile for benchmarks and target-cpu isn't a
+  /// profile setting. RUSTFLAGS also applies to the build scripts and
+  /// proc-macros, such as serde_derive, so they are built with the same
+  /// flags, which doesn't change the benchmarks' code.
+  rustflags: &'static [&'static str],
+  /// The matching flags for the C++ kernels.
+  cxxflags: &'static str,
+}
+
+const NATIVE: &str = "-Ctarget-cpu=native";
+const ABORT: &str = "-Cpanic=abort";
+
+const CONFIGS: &[Config] = &[
+  Config { name: "default", profile: "bench", rustflags: &[], cxxflags: "" },
+  Config { name: "o2", profile: "bench-o2", rustflags: &[], cxxflags: "" },
+  Config { name: "thin-lto", profile: "bench-thin", rustflags: &[], cxxflags: "" },
+  Config { name: "fat-lto", profile: "bench-fat", rustflags: &[], cxxflags: "" },
+  Config { name: "cgu1", profile: "bench-cgu1", rustflags: &[], cxxflags: "" },
+  Config { name: "abort", profile: "bench", rustflags: &[ABORT], cxxflags: "" },
+  Config { name: "native", profile: "bench", rustflags: &[NATIVE], cxxflags: "-march=native" },
+  Config { name: "max", profile: "bench-max", rustflags: &[ABORT, NATIVE],
+    cxxflags: "-march=native" },
+];
+
+fn target_dir() -> PathBuf {
+  let dir = env::var_os("CARGO_TARGET_DIR").map_or_else(|| PathBuf::from("target"), PathBuf::from);
+  env::current_dir().map(|cwd| cwd.join(&dir)).unwrap_or(dir)
+}
+
+/// Run the benchmarks under one configuration and return its run.
+fn run_config(config: &Config, criterion_args: &[String]) -> Result<Run, String> {
+  let dir = target_dir().join("matrix").join(config.name);
+  let mut rustflags: Vec<String> = env::var("RUSTFLAGS").ok().into_iter().collect();
+  rustflags.extend(config.rustflags.iter().map(|f| f.to_string()));
+  let mut cxxflags: Vec<String> = env::var("CXXFLAGS").ok().into_iter().collect();
+  cxxflags.push(config.cxxflags.to_string());
+  println!("Running {} with profile {} and RUSTFLAGS={}", config.name, config.profile,
+           rustflags.join(" "));
+  let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+  let status = Command::new(cargo)
+      .args(["bench", "--profile", config.profile, "--bench", "bench", "--"])
+      .args(criterion_args)
+      .env("CARGO_TARGET_DIR", &dir)
+      .env("RUSTFLAGS", rustflags.join(" ").trim())
+      .env("CXXFLAGS", cxxflags.join(" ").trim())
+      .env("RUST_BENCH_CONFIG", config.name)
+      .env_remove("CRITERION_HOME")
+      .status()
+      .map_err(|err| format!("Can't run cargo: {err}"))?;
+  if !status.success() {
+    return Err(format!("The benchmarks failed under {}", config.name));
+  }
+  let path = results::latest_run_in(&dir.join("rust-bench").join("runs"))
+      .map_err(|err| format!("No run for {}: {err}", config.name))?;
+  Run::read(&path).map_err(|err| format!("Can't read {}: {err}", path.display()))
+}
+
+/// Print each benchmark's time under the first configuration and its
+/// ratio to that time under the others.
+fn print_summary(runs: &[Run]) {
+  let Some(base) = runs.first() else {
+    return;
+  };
+  let width = base.benchmarks.iter().map(|b| b.id.len()).max().unwrap_or(0).max(9);
+  let names: Vec<String> = runs.iter()
+      .map(|r| format!("{:>10}", r.config.as_deref().unwrap_or(""))).collect();
+  println!("{:<width$}  {}", "benchmark", names.join("  "));
+  for bench in &base.benchmarks {
+    let mut columns = vec![format!("{:>10}", format_time(bench.estimate))];
+    for run in &runs[1..] {
+      let other = run.benchmarks.iter().find(|b| b.id == bench.id);
+      columns.push(match other {
+        Some(other) => format!("{:>9.2}x", other.estimate / bench.estimate),
+        None => format!("{:>10}", "-"),
+      });
+    }
+    println!("{:<width$}  {}", bench.id, columns.join("  "));
+  }
+}
+
+/// Write the CSVs of the runs as one file with a header.
+fn write_csv(runs: &[Run], path: &Path) -> Result<(), String> {
+  let mut text = String::new();
+  for (i, run) in runs.iter().enumerate() {
+    let csv = run.to_csv();
+    let body = if i == 0 { &csv[..] } else { csv.split_once('\n').map_or("", |(_, rest)| rest) };
+    text.push_str(body);
+  }
+  fs::create_dir_all(path.parent().unwrap())
+      .and_then(|_| fs::write(path, text))
+      .map_err(|err| format!("Can't write {}: {err}", path.display()))
+}
+
+fn run() -> Result<(), String> {
+  let mut selected: Vec<&Config> = CONFIGS.iter().collect();
+  let mut criterion_args = Vec::new();
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--configs" => {
+        let names = args.next().ok_or("--configs needs a list of names")?;
+        selected = names.split(',').map(|name| CONFIGS.iter().find(|c| c.name == name)
+            .ok_or_else(|| format!("Unknown configuration {name}")))
+            .collect::<Result<_, _>>()?;
+      }
+      "--list" => {
+        for config in CONFIGS {
+          println!("{:<10} profile {:<10} {} {}", config.name, config.profile,
+                   config.rustflags.join(" "), config.cxxflags);
+        }
+        return Ok(());
+      }
+      "--" => criterion_args.extend(args.by_ref()),
+      _ => return Err(format!("Unknown argument {arg}")),
+    }
+  }
+  let mut runs = Vec::new();
+  for config in selected {
+    runs.push(run_config(config, &criterion_args)?);
+  }
+  print_summary(&runs);
+  if let Some(first) = runs.first() {
+    let stamp = first.run_id.split('-').take(2).collect::<Vec<_>>().join("-");
+    let path = target_dir().join("rust-bench").join("matrix").join(format!("{stamp}.csv"));
+    write_csv(&runs, &path)?;
+    println!("Saved the matrix in {}", path.display());
+  }
+  Ok(())
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,161 @@
+// Copyright by Owen O'Malley 2024
+
+// Sweep the number of classes and the period of a repeated shuffled
+// order of trait objects to find where the indirect branch predictor
+// stops learning the order, and estimate how much history it holds.
+//
+//   cargo run --release --bin predictor -- [--classes 2,4,...] [--periods 8,16,...]
+//       [--samples N]
+//
+// Each point of the grid and each random baseline is saved as a
+// benchmark of the run, and the knees and the estimate are saved as the
+// run's predictor report, so that machines can be compared. The noise
+// controls, such as RUST_BENCH_PIN, apply as they do to the benchmarks.
+
+use std::env;
+use std::hint::black_box;
+use std::process::ExitCode;
+use std::time::{Duration, Instant, SystemTime};
+use rust_bench::kernels::dispatch::{ids_from_layout, iter_objs, objs_from_ids, Processor};
+use rust_bench::machine::Fingerprint;
+use rust_bench::noise;
+use rust_bench::patterns::Layout;
+use rust_bench::predictor::{self, ClassSweep, PredictorReport};
+use rust_bench::results::{self, BenchResult, Measurement, Run};
+
+/// The shortest time for each sample.
+const SAMPLE_TIME: Duration = Duration::from_millis(2);
+
+/// Time iter_objs over the objects and return the nanoseconds per call
+/// of each sample.
+fn time(objs: &[Box<dyn Processor>], samples: usize) -> Vec<f64> {
+  for _ in 0..3 {
+    black_box(iter_objs(black_box(objs)));
+  }
+  let start = Instant::now();
+  black_box(iter_objs(black_box(objs)));
+  let once = start.elapsed().max(Duration::from_nanos(1));
+  let iterations = (SAMPLE_TIME.as_nanos() / once.as_nanos()).max(1) as u32;
+  (0..samples).map(|_| {
+    let start = Instant::now();
+    for _ in 0..iterations {
+      black_box(iter_objs(black_box(objs)));
+    }
+    start.elapsed().as_nanos() as f64 / iterations as f64 / objs.len() as f64
+  }).collect()
+}
+
+/// Time the layout and record it as a benchmark.
+fn measure(layout: Layout, classes: usize, size: usize, samples: usize, function: &str,
+           parameter: Option<&str>, benchmarks: &mut Vec<BenchResult>) -> f64 {
+  let ids = ids_from_layout(layout, classes, size, rust_bench::data_seed());
+  let samples = time(&objs_from_ids(&ids), samples);
+  let group = match layout {
+    Layout::Random => "predictor random",
+    _ => "predictor window",
+  };
+  let id = match parameter {
+    Some(parameter) => format!("{group}/{function}/{parameter}"),
+    None => format!("{group}/{function}"),
+  };
+  // Scale the samples to the whole sequence so that the results are per
+  // iteration like Criterion's.
+  let result = BenchResult::from_samples(&id, group, Some(function), parameter, size as u64,
+      samples.iter().map(|s| s * size as f64).collect(), None);
+  let per_call = result.estimate / size as f64;
+  benchmarks.push(result);
+  per_call
+}
+
+fn parse_list(flag: &str, value: Option<String>) -> Result<Vec<usize>, String> {
+  value.ok_or_else(|| format!("{flag} needs a list"))?.split(',')
+      .map(|v| v.trim().parse().map_err(|_| format!("Bad number {v} for {flag}"))).collect()
+}
+
+fn print_report(report: &PredictorReport, periods: &[usize]) {
+  let columns: Vec<String> = periods.iter().map(|p| format!("{p:>6}")).collect();
+  println!("ns per call by period");
+  println!("{:>7}  {}  {:>6}  knee", "classes", columns.join(""), "random");
+  for sweep in &report.sweeps {
+    let cells: Vec<String> = periods.iter().map(|p| {
+      match sweep.periods.iter().find(|(period, _)| period == p) {
+        Some((_, ns)) => format!("{ns:>6.2}"),
+        None => format!("{:>6}", "-"),
+      }
+    }).collect();
+    let knee = match sweep.knee {
+      Some(knee) => format!("{}..{}", knee.below, knee.above),
+      None => "none".to_string(),
+    };
+    println!("{:>7}  {}  {:>6.2}  {knee}", sweep.classes, cells.join(""), sweep.random_ns);
+  }
+  match report.capacity {
+    Some(capacity) => println!("Estimated indirect predictor history: about {capacity:.0} calls"),
+    None => println!("No knee in the grid, so the predictor history can't be estimated"),
+  }
+}
+
+fn run() -> Result<(), String> {
+  let mut classes = predictor::CLASSES.to_vec();
+  let mut periods = predictor::PERIODS.to_vec();
+  let mut samples = 20;
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--classes" => classes = parse_list("--classes", args.next())?,
+      "--periods" => periods = parse_list("--periods", args.next())?,
+      "--samples" => samples = args.next().and_then(|v| v.parse().ok())
+          .ok_or("--samples needs a number")?,
+      _ => return Err(format!("Unknown argument {arg}")),
+    }
+  }
+  if let Some(bad) = classes.iter().find(|c| !(1..=50).contains(*c)) {
+    return Err(format!("There are only 50 classes, not {bad}"));
+  }
+  rust_bench::seed_from_env()?;
+  periods.sort_unstable();
+  if cfg!(debug_assertions) {
+    eprintln!("Without --release, the times are dominated by the unoptimized code");
+  }
+  let noise = noise::prepare();
+  println!("Noise: {}", noise.summary());
+  for warning in noise.warnings() {
+    eprintln!("Noisy machine: {warning}");
+  }
+  let started = SystemTime::now();
+  // Repeat even the longest period several times.
+  let size = (4 * periods.last().copied().unwrap_or(0)).max(10_000);
+  let mut benchmarks = Vec::new();
+  let mut sweeps = Vec::new();
+  for &count in &classes {
+    let function = format!("classes {count}");
+    let mut sweep = ClassSweep { classes: count, periods: Vec::new(), knee: None,
+      random_ns: measure(Layout::Random, count, size, samples, &function, None, &mut benchmarks) };
+    for &period in periods.iter().filter(|p| **p >= count) {
+      let ns = measure(Layout::ShuffledPeriod { period }, count, size, samples, &function,
+                       Some(&period.to_string()), &mut benchmarks);
+      sweep.periods.push((period, ns));
+    }
+    println!("Measured {count} classes");
+    sweeps.push(sweep);
+  }
+  let report = PredictorReport::new(sweeps);
+  print_report(&report, &periods);
+  let mut run = Run::new(started, results::config_from_env(), rust_bench::data_seed(),
+                         Fingerprint::collect(), Measurement::WallTime, benchmarks);
+  run.noise = Some(noise);
+  run.predictor = Some(report);
+  let dir = run.save().map_err(|err| format!("Can't save results: {err}"))?;
+  println!("Saved results in {}", dir.display());
+  Ok(())
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,60 @@
+// Copyright by Owen O'Malley 2024
+
+// Rewrite the result lists in the README from a saved run.
+//
+//   cargo run --bin report -- [RUN] [--readme PATH]
+//
+// RUN is a run directory or results.json and defaults to the latest run.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use rust_bench::report;
+use rust_bench::results::{self, Run};
+
+fn main() -> ExitCode {
+  let mut run_path = None;
+  let mut readme = PathBuf::from("README.md");
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--readme" => match args.next() {
+        Some(path) => readme = PathBuf::from(path),
+        None => {
+          eprintln!("--readme needs a path");
+          return ExitCode::FAILURE;
+        }
+      },
+      _ => run_path = Some(PathBuf::from(arg)),
+    }
+  }
+  let run = match run_path.map_or_else(results::latest_run, Ok).and_then(|p| Run::read(&p)) {
+    Ok(run) => run,
+    Err(err) => {
+      eprintln!("Can't read the run: {err}");
+      return ExitCode::FAILURE;
+    }
+  };
+  let text = match fs::read_to_string(&readme) {
+    Ok(text) => text,
+    Err(err) => {
+      eprintln!("Can't read {}: {err}", readme.display());
+      return ExitCode::FAILURE;
+    }
+  };
+  match report::rewrite(&text, &run) {
+    Ok((text, updated)) => {
+      if let Err(err) = fs::write(&readme, text) {
+        eprintln!("Can't write {}: {err}", readme.display());
+        return ExitCode::FAILURE;
+      }
+      println!("Updated {} from run {} on {}: {}", readme.display(), run.run_id,
+               run.machine.summary(), updated.join(", "));
+      ExitCode::SUCCESS
+    }
+    Err(err) => {
+      eprintln!("Can't update {}: {err}", readme.display());
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,118 @@
+// Copyright by Owen O'Malley 2024
+
+// Run the benchmarks over several seeds for their random data and report
+// how much each result moves with the input compared to the timer's noise.
+//
+//   cargo run --bin seeds -- [--seeds N] [--first SEED] [-- CRITERION ARGS...]
+//
+// The sweep runs seeds FIRST to FIRST+N-1, which default to 0 to 4. Pass
+// a filter to Criterion to sweep only some of the groups, such as
+// -- dispatch. Each seed keeps its own Criterion baselines.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, ExitCode};
+use rust_bench::report::format_value;
+use rust_bench::results::{self, Run};
+use rust_bench::seeds::{self, SeedSpread};
+
+/// Run the benchmarks with one seed and return its run.
+fn run_seed(seed: u64, criterion_args: &[String]) -> Result<Run, String> {
+  println!("Running with seed {seed}");
+  let criterion_home = rust_bench::criterion_home().with_file_name("criterion-seeds")
+      .join(seed.to_string());
+  let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+  let status = Command::new(cargo)
+      .args(["bench", "--bench", "bench", "--"])
+      .args(criterion_args)
+      .env("RUST_BENCH_SEED", seed.to_string())
+      .env("CRITERION_HOME", criterion_home)
+      .status()
+      .map_err(|err| format!("Can't run cargo: {err}"))?;
+  if !status.success() {
+    return Err(format!("The benchmarks failed with seed {seed}"));
+  }
+  let path = results::latest_run().map_err(|err| format!("No run for seed {seed}: {err}"))?;
+  let run = Run::read(&path).map_err(|err| format!("Can't read {}: {err}", path.display()))?;
+  if run.seed != seed {
+    return Err(format!("The latest run {} isn't for seed {seed}", run.run_id));
+  }
+  Ok(run)
+}
+
+fn percent(part: f64, whole: f64) -> String {
+  format!("{:.1}%", part / whole * 100.0)
+}
+
+fn print_spread(spreads: &[SeedSpread]) {
+  let width = spreads.iter().map(|s| s.id.len()).max().unwrap_or(0).max(9);
+  println!("{:<width$}  {:>10}  {:>7}  {:>7}  {:>10}  {:>10}", "benchmark", "mean", "seed ±",
+           "timer ±", "min", "max");
+  for spread in spreads {
+    let format = |value| format_value(spread.measurement, value);
+    println!("{:<width$}  {:>10}  {:>7}  {:>7}  {:>10}  {:>10}{}", spread.id,
+             format(spread.mean), percent(spread.seed_std_dev, spread.mean),
+             percent(spread.noise, spread.mean), format(spread.min),
+             format(spread.max), if spread.depends_on_data() { "  *" } else { "" });
+  }
+  println!();
+  println!("* the spread across seeds is larger than the timer's noise");
+}
+
+fn write_csv(spreads: &[SeedSpread], path: &Path) -> Result<(), String> {
+  let mut text = String::from("id,seeds,unit,mean,seed_std_dev,noise,min,max\n");
+  for spread in spreads {
+    let seeds: Vec<String> = spread.seeds.iter().map(|s| s.to_string()).collect();
+    text.push_str(&format!("\"{}\",{},{},{},{},{},{},{}\n", spread.id.replace('"', "\"\""),
+                           seeds.join(" "), spread.measurement.unit(), spread.mean,
+                           spread.seed_std_dev, spread.noise, spread.min, spread.max));
+  }
+  fs::create_dir_all(path.parent().unwrap())
+      .and_then(|_| fs::write(path, text))
+      .map_err(|err| format!("Can't write {}: {err}", path.display()))
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<u64, String> {
+  value.and_then(|v| v.parse().ok()).ok_or_else(|| format!("{flag} needs a number"))
+}
+
+fn run() -> Result<(), String> {
+  let mut count = 5;
+  let mut first = 0;
+  let mut criterion_args = Vec::new();
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--seeds" => count = parse_number("--seeds", args.next())?,
+      "--first" => first = parse_number("--first", args.next())?,
+      "--" => criterion_args.extend(args.by_ref()),
+      _ => return Err(format!("Unknown argument {arg}")),
+    }
+  }
+  if count < 2 {
+    return Err("The sweep needs at least two seeds".to_string());
+  }
+  let mut runs = Vec::new();
+  for seed in first..first + count {
+    runs.push(run_seed(seed, &criterion_args)?);
+  }
+  let spreads = seeds::spread(&runs);
+  println!();
+  print_spread(&spreads);
+  let stamp = runs[0].run_id.split('-').take(2).collect::<Vec<_>>().join("-");
+  let path = results::runs_dir().parent().unwrap().join("seeds").join(format!("{stamp}.csv"));
+  write_csv(&spreads, &path)?;
+  println!("Saved the spread in {}", path.display());
+  Ok(())
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::FAILURE
+    }
+  }
+}
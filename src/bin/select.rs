@@ -0,0 +1,62 @@
+// Copyright by Owen O'Malley 2024
+
+// List or run the benchmarks by category, group, variant, and parameter.
+//
+//   cargo run --bin select -- [--list] [--category NAME,...] [--group NAME,...]
+//       [--variant NAME,...] [--params RANGE] [-- CRITERION ARGS...]
+//
+// For example, --category dispatch --group "dispatch random" --variant objs
+// --params 8..=12 runs the random objects with 8 to 12 classes. It passes
+// the selection to cargo bench in the environment variables that
+// rust_bench::select describes.
+
+use std::env;
+use std::process::{Command, ExitCode};
+use rust_bench::select::ParamRange;
+
+fn run() -> Result<bool, String> {
+  let mut vars = Vec::new();
+  let mut criterion_args = Vec::new();
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    let var = match arg.as_str() {
+      "--list" => {
+        vars.push(("RUST_BENCH_LIST", "1".to_string()));
+        continue;
+      }
+      "--" => {
+        criterion_args.extend(args.by_ref());
+        continue;
+      }
+      "--category" => "RUST_BENCH_CATEGORY",
+      "--group" => "RUST_BENCH_GROUP",
+      "--variant" => "RUST_BENCH_VARIANT",
+      "--params" => "RUST_BENCH_PARAMS",
+      _ => return Err(format!("Unknown argument {arg}")),
+    };
+    let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+    if var == "RUST_BENCH_PARAMS" {
+      value.parse::<ParamRange>()?;
+    }
+    vars.push((var, value));
+  }
+  let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+  let status = Command::new(cargo)
+      .args(["bench", "--bench", "bench", "--"])
+      .args(criterion_args)
+      .envs(vars)
+      .status()
+      .map_err(|err| format!("Can't run cargo: {err}"))?;
+  Ok(status.success())
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(true) => ExitCode::SUCCESS,
+    Ok(false) => ExitCode::FAILURE,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,101 @@
+// Copyright by Owen O'Malley 2024
+
+// Read the sizes of the CPU's data caches, so that the benchmarks can
+// sweep the input size across each of the cache boundaries.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+const CACHE_DIR: &str = "/sys/devices/system/cpu/cpu0/cache";
+
+/// One of the caches that holds data for cpu0.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cache {
+  pub level: u32,
+  /// Either "Data" or "Unified".
+  pub kind: String,
+  pub size_bytes: usize,
+}
+
+/// Parse the sizes in /sys, such as "48K" or "32M".
+fn parse_size(text: &str) -> Option<usize> {
+  let text = text.trim();
+  let (digits, multiplier) = match text.chars().last()? {
+    'K' => (&text[..text.len() - 1], 1 << 10),
+    'M' => (&text[..text.len() - 1], 1 << 20),
+    'G' => (&text[..text.len() - 1], 1 << 30),
+    _ => (text, 1),
+  };
+  digits.parse::<usize>().ok().map(|x| x * multiplier)
+}
+
+fn read_cache(dir: &Path) -> Option<Cache> {
+  let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+  let kind = read("type")?.trim().to_string();
+  if kind == "Instruction" {
+    return None;
+  }
+  Some(Cache { level: read("level")?.trim().parse().ok()?,
+    kind,
+    size_bytes: parse_size(&read("size")?)? })
+}
+
+/// Read the data caches sorted by level. This is empty on platforms
+/// without /sys, in which case every size is reported as DRAM.
+pub fn caches() -> Vec<Cache> {
+  let mut result: Vec<Cache> = match fs::read_dir(CACHE_DIR) {
+    Ok(entries) => entries.flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with("index"))
+        .filter_map(|e| read_cache(&e.path()))
+        .collect(),
+    Err(_) => Vec::new(),
+  };
+  result.sort_by_key(|c| c.level);
+  result
+}
+
+/// The smallest level of the memory hierarchy that a working set of
+/// the given size fits in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Residency {
+  Cache(u32),
+  Dram,
+}
+
+impl fmt::Display for Residency {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Residency::Cache(level) => write!(f, "L{level}"),
+      Residency::Dram => write!(f, "DRAM"),
+    }
+  }
+}
+
+/// Find where a working set of the given number of bytes fits.
+pub fn residency(bytes: usize, caches: &[Cache]) -> Residency {
+  caches.iter().find(|c| bytes <= c.size_bytes)
+      .map_or(Residency::Dram, |c| Residency::Cache(c.level))
+}
+
+/// The sizes in elements for the sweeps, which go up by a factor of 4
+/// from 1K to max.
+pub fn sweep_sizes(max: usize) -> Vec<usize> {
+  std::iter::successors(Some(1usize << 10), |x| Some(x * 4))
+      .take_while(|x| *x <= max)
+      .collect()
+}
+
+/// Print the caches and where each size in the sweep fits, so that the
+/// drops in throughput can be matched to the cache boundaries.
+pub fn describe_sweep(name: &str, sizes: &[usize], element_bytes: usize) {
+  let caches = caches();
+  let levels: Vec<String> = caches.iter()
+      .map(|c| format!("L{} {} KiB", c.level, c.size_bytes >> 10)).collect();
+  println!("{name}: caches [{}]", levels.join(", "));
+  for size in sizes {
+    let bytes = size * element_bytes;
+    println!("{name}: {size} elements = {} KiB in {}", bytes >> 10, residency(bytes, &caches));
+  }
+}
@@ -0,0 +1,153 @@
+// Copyright by Owen O'Malley 2024
+
+// Compare two saved runs, such as the same benchmarks built with two
+// versions of rustc. A benchmark has regressed when its estimate got
+// slower by more than the noise threshold and a Mann-Whitney U test on
+// Criterion's samples says that the difference is significant.
+
+use std::collections::HashMap;
+use std::fmt;
+use crate::results::{BenchResult, Run};
+
+/// Whether a benchmark changed between the two runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+  Regressed,
+  Improved,
+  Unchanged,
+}
+
+impl fmt::Display for Verdict {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Verdict::Regressed => write!(f, "regressed"),
+      Verdict::Improved => write!(f, "improved"),
+      Verdict::Unchanged => write!(f, "unchanged"),
+    }
+  }
+}
+
+/// The comparison of one benchmark that is in both runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+  pub id: String,
+  /// The estimates in the unit of the runs' measurement.
+  pub base: f64,
+  pub new: f64,
+  /// The relative change in the estimate, so 0.1 is 10% slower.
+  pub change: f64,
+  /// The two-sided p-value of the Mann-Whitney U test.
+  pub p_value: f64,
+  pub verdict: Verdict,
+}
+
+/// The comparison of two runs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comparison {
+  /// The changes sorted from the worst regression to the best improvement.
+  pub changes: Vec<Change>,
+  /// The benchmarks that are only in the base run.
+  pub removed: Vec<String>,
+  /// The benchmarks that are only in the new run.
+  pub added: Vec<String>,
+}
+
+impl Comparison {
+  pub fn regressions(&self) -> impl Iterator<Item = &Change> {
+    self.changes.iter().filter(|c| c.verdict == Verdict::Regressed)
+  }
+}
+
+/// The complementary error function, from Numerical Recipes, which has
+/// a relative error below 1.2e-7.
+fn erfc(x: f64) -> f64 {
+  let z = x.abs();
+  let t = 1.0 / (1.0 + 0.5 * z);
+  let r = t * (-z * z - 1.265_512_23 + t * (1.000_023_68 + t * (0.374_091_96
+      + t * (0.096_784_18 + t * (-0.186_288_06 + t * (0.278_868_07 + t * (-1.135_203_98
+      + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))))).exp();
+  if x >= 0.0 { r } else { 2.0 - r }
+}
+
+/// The two-sided p-value of the Mann-Whitney U test that the two
+/// samples come from the same distribution. It uses the normal
+/// approximation with the correction for ties, which is accurate once
+/// each sample has more than about 10 values, and Criterion takes at
+/// least 10 samples.
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> f64 {
+  let (n1, n2) = (a.len() as f64, b.len() as f64);
+  if a.is_empty() || b.is_empty() {
+    return 1.0;
+  }
+  let mut values: Vec<(f64, bool)> = a.iter().map(|x| (*x, true))
+      .chain(b.iter().map(|x| (*x, false))).collect();
+  values.sort_by(|x, y| x.0.total_cmp(&y.0));
+  // Give each run of tied values the average of their ranks.
+  let mut rank_sum = 0.0;
+  let mut ties = 0.0;
+  let mut i = 0;
+  while i < values.len() {
+    let j = i + values[i..].iter().take_while(|v| v.0 == values[i].0).count();
+    let rank = (i + j + 1) as f64 / 2.0;
+    rank_sum += rank * values[i..j].iter().filter(|v| v.1).count() as f64;
+    let t = (j - i) as f64;
+    ties += t * t * t - t;
+    i = j;
+  }
+  let n = n1 + n2;
+  let u = rank_sum - n1 * (n1 + 1.0) / 2.0;
+  let mean = n1 * n2 / 2.0;
+  let variance = n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+  if variance <= 0.0 {
+    return 1.0;
+  }
+  let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+  erfc(z / std::f64::consts::SQRT_2).min(1.0)
+}
+
+/// Whether the samples are all the same, as with simulated instruction
+/// counts.
+fn is_exact(samples: &[f64]) -> bool {
+  !samples.is_empty() && samples.iter().all(|s| *s == samples[0])
+}
+
+/// Compare one benchmark. It has only changed if the relative change
+/// is larger than the threshold and the p-value is below alpha. When
+/// both benchmarks have exact samples, any difference is significant.
+pub fn compare_result(base: &BenchResult, new: &BenchResult, threshold: f64,
+                      alpha: f64) -> Change {
+  let change = new.estimate / base.estimate - 1.0;
+  let p_value = if is_exact(&base.samples) && is_exact(&new.samples) {
+    if base.samples[0] == new.samples[0] { 1.0 } else { 0.0 }
+  } else {
+    mann_whitney(&base.samples, &new.samples)
+  };
+  let verdict = if p_value >= alpha || change.abs() <= threshold {
+    Verdict::Unchanged
+  } else if change > 0.0 {
+    Verdict::Regressed
+  } else {
+    Verdict::Improved
+  };
+  Change { id: new.id.clone(), base: base.estimate, new: new.estimate, change,
+    p_value, verdict }
+}
+
+/// Compare every benchmark that is in both runs.
+pub fn compare(base: &Run, new: &Run, threshold: f64, alpha: f64) -> Comparison {
+  let base_results: HashMap<&str, &BenchResult> = base.benchmarks.iter()
+      .map(|r| (r.id.as_str(), r)).collect();
+  let new_ids: HashMap<&str, &BenchResult> = new.benchmarks.iter()
+      .map(|r| (r.id.as_str(), r)).collect();
+  let mut result = Comparison::default();
+  for bench in &new.benchmarks {
+    match base_results.get(bench.id.as_str()) {
+      Some(old) => result.changes.push(compare_result(old, bench, threshold, alpha)),
+      None => result.added.push(bench.id.clone()),
+    }
+  }
+  result.removed = base.benchmarks.iter().filter(|r| !new_ids.contains_key(r.id.as_str()))
+      .map(|r| r.id.clone()).collect();
+  result.changes.sort_by(|a, b| b.change.total_cmp(&a.change));
+  result
+}
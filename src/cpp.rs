@@ -0,0 +1,96 @@
+// Copyright by Owen O'Malley 2024
+
+// Safe wrappers around the C++ kernels in c++/kernels.cc, which the
+// build script compiles and links into this crate.
+
+use std::ffi::c_void;
+use crate::patterns::Layout;
+use crate::verify::{check_variants, expect_i32};
+
+extern "C" {
+  fn cpp_processors_new(ids: *const i32, len: usize) -> *mut c_void;
+  fn cpp_processors_free(data: *mut c_void);
+  fn cpp_iter_objs(data: *const c_void) -> i32;
+  fn cpp_switch_dispatch(ids: *const i32, len: usize) -> i32;
+  fn cpp_sum_via_index(data: *const i32, len: usize) -> i32;
+  fn cpp_sum_via_for(data: *const i32, len: usize) -> i32;
+  fn cpp_sum_via_accumulate(data: *const i32, len: usize) -> i32;
+}
+
+/// The number of Processor classes that the C++ code defines.
+pub const CLASSES: usize = 20;
+
+/// A C++ vector of unique_ptr<Processor> objects.
+pub struct Processors {
+  ptr: *mut c_void,
+}
+
+impl Processors {
+  /// Create a C++ object for each class id, which must be less than CLASSES.
+  pub fn new(ids: &[i32]) -> Self {
+    assert!(ids.iter().all(|x| (0..CLASSES as i32).contains(x)), "Bad class id");
+    // SAFETY: the ids are all valid classes and the slice is live for the call.
+    Processors { ptr: unsafe { cpp_processors_new(ids.as_ptr(), ids.len()) } }
+  }
+
+  /// Sum the objects using virtual dispatch in C++.
+  pub fn iter_objs(&self) -> i32 {
+    // SAFETY: ptr came from cpp_processors_new and hasn't been freed.
+    unsafe { cpp_iter_objs(self.ptr) }
+  }
+}
+
+impl Drop for Processors {
+  fn drop(&mut self) {
+    // SAFETY: ptr came from cpp_processors_new and is only freed here.
+    unsafe { cpp_processors_free(self.ptr) }
+  }
+}
+
+/// Sum the class ids' values using a C++ switch statement.
+pub fn switch_dispatch(ids: &[i32]) -> i32 {
+  // SAFETY: the C++ code only reads len values from the slice.
+  unsafe { cpp_switch_dispatch(ids.as_ptr(), ids.len()) }
+}
+
+/// Sum the data with an indexed for loop in C++.
+pub fn sum_via_index(data: &[i32]) -> i32 {
+  // SAFETY: the C++ code only reads len values from the slice.
+  unsafe { cpp_sum_via_index(data.as_ptr(), data.len()) }
+}
+
+/// Sum the data with a range based for loop in C++.
+pub fn sum_via_for(data: &[i32]) -> i32 {
+  // SAFETY: the C++ code only reads len values from the slice.
+  unsafe { cpp_sum_via_for(data.as_ptr(), data.len()) }
+}
+
+/// Sum the data with std::accumulate in C++.
+pub fn sum_via_accumulate(data: &[i32]) -> i32 {
+  // SAFETY: the C++ code only reads len values from the slice.
+  unsafe { cpp_sum_via_accumulate(data.as_ptr(), data.len()) }
+}
+
+/// Check that the virtual dispatch and the switch agree.
+pub fn check_dispatch(ids: &[i32], objs: &Processors) {
+  check_variants("cpp dispatch", objs.iter_objs(), &[("switch", switch_dispatch(ids))]);
+}
+
+/// Check that the C++ sums agree with the Rust sum.
+pub fn check_elements(data: &[i32]) {
+  let expected = expect_i32("cpp element", data.iter().map(|x| *x as i64).sum());
+  check_variants("cpp element", expected, &[("index", sum_via_index(data)),
+    ("for", sum_via_for(data)), ("accumulate", sum_via_accumulate(data))]);
+}
+
+/// Check the variants over generated data.
+pub fn verify(seed: u64, size: usize) {
+  for layout in [Layout::Random, Layout::TickTock] {
+    for number_of_classes in [1, 7, CLASSES] {
+      let ids: Vec<i32> = layout.generate(number_of_classes, size, seed).into_iter()
+          .map(|x| x as i32).collect();
+      check_dispatch(&ids, &Processors::new(&ids));
+    }
+  }
+  check_elements(&crate::random_vec_in(-100_000..100_000, size, seed));
+}
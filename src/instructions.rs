@@ -0,0 +1,147 @@
+// Copyright by Owen O'Malley 2024
+
+// Count the instructions of each benchmark instead of timing it, so that
+// a noisy machine, such as a shared CI runner, still gives a stable
+// signal for regressions. With RUST_BENCH_INSTRUCTIONS set, the
+// benchmarks skip Criterion and either count the user mode instructions
+// with the perf counters or, when they run under Valgrind's callgrind,
+// count the instructions and simulate the caches. The simulated counts
+// are exact, so they are the same on every run of the same binary.
+//
+// Under callgrind, each benchmark turns on the instrumentation, warms
+// up, zeroes the counts, runs, and dumps the counts with its id as the
+// trigger. The dumps go in $RUST_BENCH_CALLGRIND_DIR, which is read at
+// the end of the run. The instructions tool sets all of that up.
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::perf::Counters;
+
+// The requests that Valgrind's valgrind.h and callgrind.h define.
+const RUNNING_ON_VALGRIND: usize = 0x1001;
+const CALLGRIND_BASE: usize = ((b'C' as usize) << 24) | ((b'T' as usize) << 16);
+const CALLGRIND_ZERO_STATS: usize = CALLGRIND_BASE + 1;
+const CALLGRIND_DUMP_STATS_AT: usize = CALLGRIND_BASE + 3;
+const CALLGRIND_START_INSTRUMENTATION: usize = CALLGRIND_BASE + 4;
+const CALLGRIND_STOP_INSTRUMENTATION: usize = CALLGRIND_BASE + 5;
+
+/// Make a Valgrind client request, which is a special sequence of
+/// instructions that does nothing and returns the default outside of
+/// Valgrind.
+#[cfg(target_arch = "x86_64")]
+fn client_request(default: usize, request: usize, arg: usize) -> usize {
+  let args: [usize; 6] = [request, arg, 0, 0, 0, 0];
+  let result;
+  // SAFETY: the rotations of rdi add up to 128 bits, which leaves it
+  // unchanged, and Valgrind only reads args.
+  unsafe {
+    std::arch::asm!("rol rdi, 3", "rol rdi, 13", "rol rdi, 61", "rol rdi, 51", "xchg rbx, rbx",
+                    in("rax") args.as_ptr(), inout("rdx") default => result,
+                    options(nostack));
+  }
+  result
+}
+
+#[cfg(target_arch = "aarch64")]
+fn client_request(default: usize, request: usize, arg: usize) -> usize {
+  let args: [usize; 6] = [request, arg, 0, 0, 0, 0];
+  let result;
+  // SAFETY: the rotations of x12 add up to 128 bits, which leaves it
+  // unchanged, and Valgrind only reads args.
+  unsafe {
+    std::arch::asm!("ror x12, x12, #3", "ror x12, x12, #13", "ror x12, x12, #51",
+                    "ror x12, x12, #61", "orr x10, x10, x10",
+                    in("x4") args.as_ptr(), inout("x3") default => result,
+                    options(nostack));
+  }
+  result
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn client_request(default: usize, _request: usize, _arg: usize) -> usize {
+  default
+}
+
+/// Whether the process is running under Valgrind.
+pub fn running_on_valgrind() -> bool {
+  client_request(0, RUNNING_ON_VALGRIND, 0) != 0
+}
+
+/// Turn on callgrind's instrumentation, which is off at the start.
+pub fn start_instrumentation() {
+  client_request(0, CALLGRIND_START_INSTRUMENTATION, 0);
+}
+
+pub fn stop_instrumentation() {
+  client_request(0, CALLGRIND_STOP_INSTRUMENTATION, 0);
+}
+
+pub fn zero_stats() {
+  client_request(0, CALLGRIND_ZERO_STATS, 0);
+}
+
+/// Dump the counts since they were last zeroed with the name as the
+/// trigger.
+pub fn dump_stats_at(name: &str) {
+  let name = CString::new(name.replace('\0', " ")).unwrap();
+  client_request(0, CALLGRIND_DUMP_STATS_AT, name.as_ptr() as usize);
+}
+
+/// The counts from one callgrind dump.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Events {
+  pub instructions: u64,
+  pub data_reads: u64,
+  pub data_writes: u64,
+  /// The simulated first level data cache misses on reads.
+  pub l1d_misses: u64,
+  /// The simulated last level cache misses on data reads.
+  pub ll_misses: u64,
+}
+
+impl Events {
+  /// Convert the counts of the given iterations to counts per element.
+  pub fn per_element(&self, iterations: u64, elements: u64) -> Counters {
+    let total = (iterations * elements.max(1)) as f64;
+    Counters { instructions: Some(self.instructions as f64 / total),
+      l1d_accesses: Some((self.data_reads + self.data_writes) as f64 / total),
+      l1d_misses: Some(self.l1d_misses as f64 / total),
+      llc_misses: Some(self.ll_misses as f64 / total),
+      ..Default::default() }
+  }
+}
+
+/// Parse a callgrind dump into its trigger and its total counts. It
+/// returns None for the dumps without a client request, such as the one
+/// at the end of the program.
+pub fn parse_dump(text: &str) -> Option<(String, Events)> {
+  let mut trigger = None;
+  let mut names = Vec::new();
+  let mut totals = Vec::new();
+  for line in text.lines() {
+    if let Some(desc) = line.strip_prefix("desc: Trigger: Client Request: ") {
+      trigger = Some(desc.trim().to_string());
+    } else if let Some(events) = line.strip_prefix("events:") {
+      names = events.split_whitespace().collect();
+    } else if let Some(values) = line.strip_prefix("totals:").or(line.strip_prefix("summary:")) {
+      totals = values.split_whitespace().filter_map(|v| v.parse::<u64>().ok()).collect();
+    }
+  }
+  let count = |name: &str| names.iter().position(|n| *n == name)
+      .and_then(|i| totals.get(i).copied()).unwrap_or(0);
+  Some((trigger?, Events { instructions: count("Ir"), data_reads: count("Dr"),
+    data_writes: count("Dw"), l1d_misses: count("D1mr"), ll_misses: count("DLmr") }))
+}
+
+/// Read every callgrind dump in the directory by trigger.
+pub fn read_dumps(dir: &Path) -> io::Result<HashMap<String, Events>> {
+  let mut result = HashMap::new();
+  for entry in fs::read_dir(dir)? {
+    let text = fs::read_to_string(entry?.path())?;
+    result.extend(parse_dump(&text));
+  }
+  Ok(result)
+}
@@ -0,0 +1,692 @@
+// Copyright by Owen O'Malley 2024
+
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use paste::paste;
+use crate::patterns::Layout;
+use crate::verify::{check_variants, expect_i32};
+
+/// A utility function to translate the digits via a match
+pub fn map_digit(v: i32) -> i32 {
+  match v {
+    0 => 1,
+    1 => 4,
+    2 => 3,
+    3 => 5,
+    4 => 7,
+    5 => 11,
+    6 => 13,
+    7 => 17,
+    8 => 19,
+    9 => 23,
+    _ => panic!("Bad digit {v}"),
+  }
+}
+
+/// Use a passed in function to translate
+pub fn iter_func(data: &[i32], func: fn(i32) -> i32) -> i32 {
+  data.iter().map(|v| func(*v)).sum()
+}
+
+/// Use templates to remove the function dispatch costs.
+pub fn iter_func_template<F>(data: &[i32], func: F) -> i32
+  where F: Fn(i32) -> i32 {
+  data.iter().map(|v| func(*v)).sum()
+}
+
+/// Iterate and dispatch via an array of lambdas.
+pub fn iter_lambdas(data: &[i32], funcs: &[fn() -> i32]) -> i32 {
+  data.iter().map(|v| funcs[*v as usize]()).sum()
+}
+
+pub trait Processor {
+  fn process(&self) -> i32;
+}
+
+/// A trait with several methods that are called in sequence, which
+/// makes each object's vtable larger.
+pub trait MultiProcessor {
+  fn base(&self) -> i32;
+  fn scale(&self) -> i32;
+  fn offset(&self) -> i32;
+}
+
+/// Define a class that implements the trait and can
+/// handle all the inputs.
+#[derive(Debug)]
+pub struct GeneralProcessor {
+  x: i32,
+}
+
+impl GeneralProcessor {
+  pub fn from(i: i32) -> Self {
+    GeneralProcessor {x: map_digit(i)}
+  }
+}
+
+impl Processor for GeneralProcessor {
+  fn process(&self) -> i32 {
+    self.x
+  }
+}
+
+macro_rules! define_structs {
+    ( $({$id:literal, $value:literal}),* ) => {
+      paste!{
+
+        $(// Define the struct Processor<id> and its implementation
+          pub struct [<Processor $id>] {
+            // nothing
+          }
+
+          impl Processor for [<Processor $id>] {
+            fn process(&self) -> i32 {
+              $value
+            }
+          }
+
+          impl MultiProcessor for [<Processor $id>] {
+            fn base(&self) -> i32 {
+              $value
+            }
+
+            fn scale(&self) -> i32 {
+              2
+            }
+
+            fn offset(&self) -> i32 {
+              $id
+            }
+        })*
+
+        // Define the equivalent table of functions
+        pub const PROCESSOR_FUNCS: &[fn() -> i32] = &[$(|| $value,)*];
+
+        pub fn processor_from_i32(i: i32) -> Box<dyn Processor> {
+          match i {
+            $($id => Box::new([<Processor $id>]{}) as Box<dyn Processor>,)*
+            _ => panic!("Bad name {i}"),
+          }
+        }
+
+        pub fn multi_from_i32(i: i32) -> Box<dyn MultiProcessor> {
+          match i {
+            $($id => Box::new([<Processor $id>]{}) as Box<dyn MultiProcessor>,)*
+            _ => panic!("Bad name {i}"),
+          }
+        }
+
+        // Define the equivalent closures, which each capture part of their
+        // value. If their code were the same, the compiler would merge
+        // them and every call would have the same target.
+        pub fn closure_from_i32(i: i32) -> Box<dyn Fn() -> i32> {
+          match i {
+            $($id => {
+              let state = $value - $id;
+              Box::new(move || state + $id) as Box<dyn Fn() -> i32>
+            })*
+            _ => panic!("Bad name {i}"),
+          }
+        }
+
+        define_stateful!($({$id, $value}),*);
+
+        // Define the equivalent enum
+        #[derive(FromPrimitive)]
+        pub enum BigEnum {
+          $([<Value $id>],)*
+        }
+
+        impl Processor for BigEnum {
+          fn process(&self) -> i32 {
+            match self {
+              $(BigEnum::[<Value $id>] => $value,)*
+            }
+          }
+        }
+      }
+    }
+}
+
+macro_rules! define_stateful {
+    ( $({$id:literal, $value:literal}),* ) => {
+      paste!{
+
+        $(// Define the struct Stateful<id>, which holds part of its value so
+          // that each call reads the object.
+          #[repr(transparent)]
+          pub struct [<Stateful $id>] {
+            state: i32,
+          }
+
+          impl Processor for [<Stateful $id>] {
+            fn process(&self) -> i32 {
+              self.state + $id
+            }
+        })*
+
+        /// The state that each class holds.
+        fn state_for(i: i32) -> i32 {
+          match i {
+            $($id => $value - $id,)*
+            _ => panic!("Bad name {i}"),
+          }
+        }
+
+        pub fn stateful_from_i32(i: i32) -> Box<dyn Processor> {
+          match i {
+            $($id => Box::new([<Stateful $id>]{ state: state_for(i) }) as Box<dyn Processor>,)*
+            _ => panic!("Bad name {i}"),
+          }
+        }
+
+        /// View a state in an arena as an object of the class.
+        fn stateful_ref(i: i32, state: &i32) -> &dyn Processor {
+          match i {
+            // SAFETY: Stateful<id> is a transparent wrapper around an i32.
+            $($id => unsafe { &*(state as *const i32 as *const [<Stateful $id>]) },)*
+            _ => panic!("Bad name {i}"),
+          }
+        }
+
+        // The same functions on the state, for the objects that hold their
+        // function pointer inline.
+        const STATE_FUNCS: &[fn(i32) -> i32] = &[$(|state| state + $id,)*];
+
+        /// The objects stored inline in an enum.
+        pub enum InlineObj {
+          $([<Class $id>]([<Stateful $id>]),)*
+        }
+
+        impl InlineObj {
+          pub fn from_i32(i: i32) -> Self {
+            match i {
+              $($id => InlineObj::[<Class $id>]([<Stateful $id>]{ state: state_for(i) }),)*
+              _ => panic!("Bad name {i}"),
+            }
+          }
+        }
+
+        impl Processor for InlineObj {
+          fn process(&self) -> i32 {
+            match self {
+              $(InlineObj::[<Class $id>](obj) => obj.process(),)*
+            }
+          }
+        }
+
+        /// The objects grouped into a Vec per class, like the archetypes of
+        /// an entity component system.
+        #[derive(Default)]
+        pub struct Archetypes {
+          $([<class $id>]: Vec<[<Stateful $id>]>,)*
+        }
+
+        impl Archetypes {
+          pub fn from_ids(ids: &[i32]) -> Self {
+            let mut result = Archetypes::default();
+            for i in ids {
+              match i {
+                $($id => result.[<class $id>].push([<Stateful $id>]{ state: state_for(*i) }),)*
+                _ => panic!("Bad name {i}"),
+              }
+            }
+            result
+          }
+
+          /// Process each class with its own loop.
+          pub fn process(&self) -> i32 {
+            0 $(+ template_objs(&self.[<class $id>]))*
+          }
+        }
+      }
+    }
+}
+
+// Define Processor0 to Processor23 using a macro.
+define_structs!({0, 1}, {1, 4}, {2, 3}, {3, 5},
+  {4, 7}, {5, 11}, {6, 13}, {7, 17}, {8, 19},
+  {9, 23}, {10, 25}, {11, 27}, {12, 29}, {13, 31},
+  {14, 33}, {15, 35}, {16, 37}, {17, 39}, {18, 41},
+  {19, 43}, {20, 45}, {21, 47}, {22, 49}, {23, 51},
+  {24, 53}, {25, 55}, {26, 57}, {27, 59}, {28, 61},
+  {29, 63}, {30, 65}, {31, 67}, {32, 69}, {33, 71},
+  {34, 73}, {35, 75}, {36, 77}, {37, 79}, {38, 81},
+  {39, 83}, {40, 85}, {41, 87}, {42, 89}, {43, 91},
+  {44, 93}, {45, 95}, {46, 97}, {47, 99}, {48, 101},
+  {49, 103});
+
+/// Create size class ids with the given layout.
+pub fn ids_from_layout(layout: Layout, classes: usize, size: usize, seed: u64) -> Vec<i32> {
+  layout.generate(classes, size, seed).into_iter().map(|x| x as i32).collect()
+}
+
+/// Create an object for each class id.
+pub fn objs_from_ids(ids: &[i32]) -> Vec<Box<dyn Processor>> {
+  ids.iter().map(|x| processor_from_i32(*x)).collect()
+}
+
+/// Create an enum for each class id.
+pub fn enums_from_ids<T: FromPrimitive>(ids: &[i32]) -> Vec<T> {
+  ids.iter().map(|x| T::from_i32(*x).unwrap_or_else(|| panic!("bad value {x}"))).collect()
+}
+
+/// Use virtual dispatch through the trait.
+pub fn iter_objs(data: &[Box<dyn Processor>]) -> i32 {
+  data.iter().map(|v| v.process()).sum()
+}
+
+/// Use virtual dispatch through references to the objects.
+pub fn iter_refs(data: &[&dyn Processor]) -> i32 {
+  data.iter().map(|v| v.process()).sum()
+}
+
+/// Use virtual dispatch through any pointer to the objects, such as Rc
+/// or Arc.
+pub fn iter_pointers<P: Deref<Target = dyn Processor>>(data: &[P]) -> i32 {
+  data.iter().map(|v| v.process()).sum()
+}
+
+/// Create an Rc to an object for each class id.
+pub fn rcs_from_ids(ids: &[i32]) -> Vec<Rc<dyn Processor>> {
+  ids.iter().map(|x| Rc::from(processor_from_i32(*x))).collect()
+}
+
+/// Create an Arc to an object for each class id.
+pub fn arcs_from_ids(ids: &[i32]) -> Vec<Arc<dyn Processor>> {
+  ids.iter().map(|x| Arc::from(processor_from_i32(*x))).collect()
+}
+
+/// Call the boxed closures, which each capture their value.
+pub fn iter_closures(data: &[Box<dyn Fn() -> i32>]) -> i32 {
+  data.iter().map(|f| f()).sum()
+}
+
+/// Create a closure for each class id.
+pub fn closures_from_ids(ids: &[i32]) -> Vec<Box<dyn Fn() -> i32>> {
+  ids.iter().map(|x| closure_from_i32(*x)).collect()
+}
+
+/// Call the three methods of each object in sequence.
+pub fn iter_multi_objs(data: &[Box<dyn MultiProcessor>]) -> i32 {
+  data.iter().map(|v| v.base() * v.scale() + v.offset()).sum()
+}
+
+/// Create a multiple method object for each class id.
+pub fn multi_objs_from_ids(ids: &[i32]) -> Vec<Box<dyn MultiProcessor>> {
+  ids.iter().map(|x| multi_from_i32(*x)).collect()
+}
+
+/// A processor that holds its class's value.
+pub struct ValueProcessor {
+  x: i32,
+}
+
+impl Processor for ValueProcessor {
+  fn process(&self) -> i32 {
+    self.x
+  }
+}
+
+/// Create a processor for the class id behind impl Trait, which hides
+/// the type but keeps the dispatch static.
+pub fn impl_from_i32(i: i32) -> impl Processor {
+  ValueProcessor { x: PROCESSOR_FUNCS[i as usize]() }
+}
+
+/// Create a boxed object that holds state for each class id.
+pub fn stateful_objs_from_ids(ids: &[i32]) -> Vec<Box<dyn Processor>> {
+  ids.iter().map(|x| stateful_from_i32(*x)).collect()
+}
+
+/// An object that holds its function pointer and state inline, which
+/// pays for the indirect call without following a pointer to the object.
+pub struct InlineFn {
+  func: fn(i32) -> i32,
+  state: i32,
+}
+
+impl InlineFn {
+  pub fn from_i32(i: i32) -> Self {
+    InlineFn { func: STATE_FUNCS[i as usize], state: state_for(i) }
+  }
+}
+
+/// Call each object's function on its state without a pointer chase.
+pub fn iter_inline_fns(data: &[InlineFn]) -> i32 {
+  data.iter().map(|obj| (obj.func)(obj.state)).sum()
+}
+
+/// Create an object with its function pointer inline for each class id.
+pub fn inline_fns_from_ids(ids: &[i32]) -> Vec<InlineFn> {
+  ids.iter().map(|x| InlineFn::from_i32(*x)).collect()
+}
+
+/// Create an object stored inline in the enum for each class id.
+pub fn inline_objs_from_ids(ids: &[i32]) -> Vec<InlineObj> {
+  ids.iter().map(|x| InlineObj::from_i32(*x)).collect()
+}
+
+/// The objects' states in one allocation, sorted by class, and the
+/// class and position of each object in the sequence.
+pub struct Arena {
+  states: Vec<i32>,
+  entries: Vec<(i32, usize)>,
+}
+
+impl Arena {
+  pub fn from_ids(ids: &[i32]) -> Self {
+    let mut order: Vec<usize> = (0..ids.len()).collect();
+    order.sort_by_key(|i| ids[*i]);
+    let mut entries = vec![(0, 0); ids.len()];
+    for (position, i) in order.iter().enumerate() {
+      entries[*i] = (ids[*i], position);
+    }
+    Arena { states: order.iter().map(|i| state_for(ids[*i])).collect(), entries }
+  }
+
+  /// The objects in the order of the sequence.
+  pub fn objs(&self) -> Vec<&dyn Processor> {
+    self.entries.iter().map(|(class, position)| stateful_ref(*class, &self.states[*position]))
+        .collect()
+  }
+}
+
+/// Create an impl Trait processor for each class id.
+pub fn impl_objs_from_ids(ids: &[i32]) -> Vec<impl Processor> {
+  ids.iter().map(|x| impl_from_i32(*x)).collect()
+}
+
+/// Use a template to inline the method call.
+pub fn template_objs<T: Processor>(data: &[T]) -> i32 {
+  data.iter().map(|v| v.process()).sum()
+}
+
+/// Test the impact of wrapping the objects with Box in a template.
+pub fn template_box_objs<T: Processor>(data: &[Box<T>]) -> i32 {
+  data.iter().map(|v| v.process()).sum()
+}
+
+#[derive(FromPrimitive)]
+pub enum ProcessorEnum {
+  Zero, One, Two, Three, Four, Five, Six, Seven, Eight, Nine,
+}
+
+impl Processor for ProcessorEnum {
+  fn process(&self) -> i32 {
+    match self {
+      ProcessorEnum::Zero => 1,
+      ProcessorEnum::One => 4,
+      ProcessorEnum::Two => 3,
+      ProcessorEnum::Three => 5,
+      ProcessorEnum::Four => 7,
+      ProcessorEnum::Five => 11,
+      ProcessorEnum::Six => 13,
+      ProcessorEnum::Seven => 17,
+      ProcessorEnum::Eight => 19,
+      ProcessorEnum::Nine => 23,
+    }
+  }
+}
+
+#[derive(FromPrimitive)]
+pub enum Enum3 {
+  Value0, Value1, Value2,
+}
+
+impl Processor for Enum3 {
+  fn process(&self) -> i32 {
+    match self {
+      Enum3::Value0 => 1,
+      Enum3::Value1 => 4,
+      Enum3::Value2 => 3,
+    }
+  }
+}
+
+pub fn iter_enum<T: Processor>(data: &[T]) -> i32 {
+  data.iter().map(|x| x.process()).sum()
+}
+
+/// The work of a payload variant, which depends on its id so that each
+/// arm of the enums and each class of objects does something different.
+#[inline(always)]
+fn payload_work(id: u32, data: &[u32]) -> i32 {
+  let result = match id % 4 {
+    0 => data.iter().fold(id, |acc, x| acc.wrapping_add(*x)),
+    1 => data.iter().fold(id, |acc, x| acc ^ x.rotate_left(id % 32)),
+    2 => data.iter().fold(id, |acc, x| acc.max(*x)),
+    _ => data.iter().fold(id, |acc, x| acc.wrapping_mul(31).wrapping_add(*x)),
+  };
+  // Keep the sums from overflowing.
+  (result & 0x3ff) as i32
+}
+
+/// Copy the start of the data into a payload.
+fn payload<const N: usize>(data: &[u32]) -> [u32; N] {
+  core::array::from_fn(|j| data[j])
+}
+
+/// The enums whose variants carry payloads of different sizes, where
+/// variant 0 carries N words and the others carry 1, 2, or 4. Like any
+/// enum, they are as large as their largest variant, while each trait
+/// object is only as large as its own payload.
+pub trait PayloadEnum<const N: usize>: Processor + Sized {
+  const VARIANTS: usize;
+
+  /// The number of words in the variant's payload.
+  fn words(id: i32) -> usize;
+
+  /// Create the variant with the start of the data as its payload.
+  fn new(id: i32, data: &[u32]) -> Self;
+
+  /// Create the trait object that does the same work as the variant.
+  fn boxed(id: i32, data: &[u32]) -> Box<dyn Processor>;
+}
+
+macro_rules! define_payload_enum {
+    ( $name:ident, $($id:literal: $words:literal),* ) => {
+      paste!{
+
+        pub enum $name<const N: usize> {
+          Variant0([u32; N]),
+          $([<Variant $id>]([u32; $words]),)*
+        }
+
+        impl<const N: usize> Processor for $name<N> {
+          fn process(&self) -> i32 {
+            match self {
+              $name::Variant0(data) => payload_work(0, data),
+              $($name::[<Variant $id>](data) => payload_work($id, data),)*
+            }
+          }
+        }
+
+        // Define the class that is equivalent to the large variant
+        pub struct [<$name Class0>]<const N: usize> {
+          data: [u32; N],
+        }
+
+        impl<const N: usize> Processor for [<$name Class0>]<N> {
+          fn process(&self) -> i32 {
+            payload_work(0, &self.data)
+          }
+        }
+
+        $(// Define the class that is equivalent to the variant
+          pub struct [<$name Class $id>] {
+            data: [u32; $words],
+          }
+
+          impl Processor for [<$name Class $id>] {
+            fn process(&self) -> i32 {
+              payload_work($id, &self.data)
+            }
+        })*
+
+        impl<const N: usize> PayloadEnum<N> for $name<N> {
+          const VARIANTS: usize = 1 + [$($id),*].len();
+
+          fn words(id: i32) -> usize {
+            match id {
+              0 => N,
+              $($id => $words,)*
+              _ => panic!("Bad variant {id}"),
+            }
+          }
+
+          fn new(id: i32, data: &[u32]) -> Self {
+            match id {
+              0 => $name::Variant0(payload(data)),
+              $($id => $name::[<Variant $id>](payload(data)),)*
+              _ => panic!("Bad variant {id}"),
+            }
+          }
+
+          fn boxed(id: i32, data: &[u32]) -> Box<dyn Processor> {
+            match id {
+              0 => Box::new([<$name Class0>]::<N> { data: payload(data) }),
+              $($id => Box::new([<$name Class $id>] { data: payload(data) }),)*
+              _ => panic!("Bad variant {id}"),
+            }
+          }
+        }
+      }
+    }
+}
+
+define_payload_enum!(Payload3, 1: 2, 2: 4);
+define_payload_enum!(Payload16,
+                    1: 2, 2: 4, 3: 1, 4: 2, 5: 4, 6: 1, 7: 2, 8: 4, 9: 1, 10: 2, 11: 4, 12: 1,
+                    13: 2, 14: 4, 15: 1);
+define_payload_enum!(Payload64,
+                    1: 2, 2: 4, 3: 1, 4: 2, 5: 4, 6: 1, 7: 2, 8: 4, 9: 1, 10: 2, 11: 4, 12: 1,
+                    13: 2, 14: 4, 15: 1, 16: 2, 17: 4, 18: 1, 19: 2, 20: 4, 21: 1, 22: 2, 23: 4,
+                    24: 1, 25: 2, 26: 4, 27: 1, 28: 2, 29: 4, 30: 1, 31: 2, 32: 4, 33: 1, 34: 2,
+                    35: 4, 36: 1, 37: 2, 38: 4, 39: 1, 40: 2, 41: 4, 42: 1, 43: 2, 44: 4, 45: 1,
+                    46: 2, 47: 4, 48: 1, 49: 2, 50: 4, 51: 1, 52: 2, 53: 4, 54: 1, 55: 2, 56: 4,
+                    57: 1, 58: 2, 59: 4, 60: 1, 61: 2, 62: 4, 63: 1);
+define_payload_enum!(Payload256,
+                    1: 2, 2: 4, 3: 1, 4: 2, 5: 4, 6: 1, 7: 2, 8: 4, 9: 1, 10: 2, 11: 4, 12: 1,
+                    13: 2, 14: 4, 15: 1, 16: 2, 17: 4, 18: 1, 19: 2, 20: 4, 21: 1, 22: 2, 23: 4,
+                    24: 1, 25: 2, 26: 4, 27: 1, 28: 2, 29: 4, 30: 1, 31: 2, 32: 4, 33: 1, 34: 2,
+                    35: 4, 36: 1, 37: 2, 38: 4, 39: 1, 40: 2, 41: 4, 42: 1, 43: 2, 44: 4, 45: 1,
+                    46: 2, 47: 4, 48: 1, 49: 2, 50: 4, 51: 1, 52: 2, 53: 4, 54: 1, 55: 2, 56: 4,
+                    57: 1, 58: 2, 59: 4, 60: 1, 61: 2, 62: 4, 63: 1, 64: 2, 65: 4, 66: 1, 67: 2,
+                    68: 4, 69: 1, 70: 2, 71: 4, 72: 1, 73: 2, 74: 4, 75: 1, 76: 2, 77: 4, 78: 1,
+                    79: 2, 80: 4, 81: 1, 82: 2, 83: 4, 84: 1, 85: 2, 86: 4, 87: 1, 88: 2, 89: 4,
+                    90: 1, 91: 2, 92: 4, 93: 1, 94: 2, 95: 4, 96: 1, 97: 2, 98: 4, 99: 1, 100: 2,
+                    101: 4, 102: 1, 103: 2, 104: 4, 105: 1, 106: 2, 107: 4, 108: 1, 109: 2, 110: 4,
+                    111: 1, 112: 2, 113: 4, 114: 1, 115: 2, 116: 4, 117: 1, 118: 2, 119: 4, 120: 1,
+                    121: 2, 122: 4, 123: 1, 124: 2, 125: 4, 126: 1, 127: 2, 128: 4, 129: 1, 130: 2,
+                    131: 4, 132: 1, 133: 2, 134: 4, 135: 1, 136: 2, 137: 4, 138: 1, 139: 2, 140: 4,
+                    141: 1, 142: 2, 143: 4, 144: 1, 145: 2, 146: 4, 147: 1, 148: 2, 149: 4, 150: 1,
+                    151: 2, 152: 4, 153: 1, 154: 2, 155: 4, 156: 1, 157: 2, 158: 4, 159: 1, 160: 2,
+                    161: 4, 162: 1, 163: 2, 164: 4, 165: 1, 166: 2, 167: 4, 168: 1, 169: 2, 170: 4,
+                    171: 1, 172: 2, 173: 4, 174: 1, 175: 2, 176: 4, 177: 1, 178: 2, 179: 4, 180: 1,
+                    181: 2, 182: 4, 183: 1, 184: 2, 185: 4, 186: 1, 187: 2, 188: 4, 189: 1, 190: 2,
+                    191: 4, 192: 1, 193: 2, 194: 4, 195: 1, 196: 2, 197: 4, 198: 1, 199: 2, 200: 4,
+                    201: 1, 202: 2, 203: 4, 204: 1, 205: 2, 206: 4, 207: 1, 208: 2, 209: 4, 210: 1,
+                    211: 2, 212: 4, 213: 1, 214: 2, 215: 4, 216: 1, 217: 2, 218: 4, 219: 1, 220: 2,
+                    221: 4, 222: 1, 223: 2, 224: 4, 225: 1, 226: 2, 227: 4, 228: 1, 229: 2, 230: 4,
+                    231: 1, 232: 2, 233: 4, 234: 1, 235: 2, 236: 4, 237: 1, 238: 2, 239: 4, 240: 1,
+                    241: 2, 242: 4, 243: 1, 244: 2, 245: 4, 246: 1, 247: 2, 248: 4, 249: 1, 250: 2,
+                    251: 4, 252: 1, 253: 2, 254: 4, 255: 1);
+
+/// Pair each id with its payload, which is the next words of the data,
+/// wrapping around at the end.
+fn payloads<E: PayloadEnum<N>, const N: usize>(ids: &[i32], data: &[u32]) -> Vec<(i32, Vec<u32>)> {
+  let mut offset = 0;
+  ids.iter().map(|id| {
+    let words = E::words(*id);
+    let payload = (offset..offset + words).map(|j| data[j % data.len()]).collect();
+    offset += words;
+    (*id, payload)
+  }).collect()
+}
+
+pub fn payload_enums<E: PayloadEnum<N>, const N: usize>(ids: &[i32], data: &[u32]) -> Vec<E> {
+  payloads::<E, N>(ids, data).iter().map(|(id, payload)| E::new(*id, payload)).collect()
+}
+
+/// Create the trait objects that are equivalent to payload_enums.
+pub fn payload_objs<E: PayloadEnum<N>, const N: usize>(ids: &[i32],
+                                                       data: &[u32]) -> Vec<Box<dyn Processor>> {
+  payloads::<E, N>(ids, data).iter().map(|(id, payload)| E::boxed(*id, payload)).collect()
+}
+
+pub const DIGIT_LAMBDAS: [fn() -> i32; 10] = [|| 1, || 4, || 3, || 5, || 7, || 11, || 13, || 17,
+  || 19, || 23];
+
+/// Check that all of the ways to translate the digits agree.
+pub fn check_digits(data: &[i32]) {
+  let expected = expect_i32("dispatch", data.iter().map(|v| map_digit(*v) as i64).sum());
+  let objs: Vec<GeneralProcessor> = data.iter().map(|x| GeneralProcessor::from(*x)).collect();
+  let box_objs: Vec<Box<GeneralProcessor>> = data.iter()
+      .map(|x| Box::new(GeneralProcessor::from(*x))).collect();
+  check_variants("dispatch", expected, &[("func", iter_func(data, map_digit)),
+    ("func template", iter_func_template(data, map_digit)),
+    ("lambdas", iter_lambdas(data, &DIGIT_LAMBDAS)),
+    ("template objs", template_objs(&objs)),
+    ("template box objs", template_box_objs(&box_objs)),
+    ("objs", iter_objs(&objs_from_ids(data))),
+    ("enum 10", iter_enum(&enums_from_ids::<ProcessorEnum>(data)))]);
+}
+
+/// Check that the trait objects, the enum, and the table of functions
+/// agree for the class ids.
+pub fn check_classes(ids: &[i32]) {
+  let expected = expect_i32("dispatch",
+                            ids.iter().map(|x| processor_from_i32(*x).process() as i64).sum());
+  let objs = objs_from_ids(ids);
+  let refs: Vec<&dyn Processor> = objs.iter().map(|o| o.as_ref()).collect();
+  check_variants("dispatch", expected, &[("objs", iter_objs(&objs)),
+    ("ref objs", iter_refs(&refs)),
+    ("rc objs", iter_pointers(&rcs_from_ids(ids))),
+    ("arc objs", iter_pointers(&arcs_from_ids(ids))),
+    ("closures", iter_closures(&closures_from_ids(ids))),
+    ("impl objs", template_objs(&impl_objs_from_ids(ids))),
+    ("stateful objs", iter_objs(&stateful_objs_from_ids(ids))),
+    ("inline fns", iter_inline_fns(&inline_fns_from_ids(ids))),
+    ("inline enum", iter_enum(&inline_objs_from_ids(ids))),
+    ("archetypes", Archetypes::from_ids(ids).process()),
+    ("arena", iter_refs(&Arena::from_ids(ids).objs())),
+    ("enum 50", iter_enum(&enums_from_ids::<BigEnum>(ids))),
+    ("lambdas", iter_lambdas(ids, PROCESSOR_FUNCS))]);
+  if ids.iter().all(|x| *x < 3) {
+    check_variants("dispatch", expected, &[("enum 3", iter_enum(&enums_from_ids::<Enum3>(ids)))]);
+  }
+  // The objects with several methods compute base * scale + offset.
+  let expected = expect_i32("dispatch", ids.iter()
+      .map(|x| (PROCESSOR_FUNCS[*x as usize]() * 2 + x) as i64).sum());
+  check_variants("dispatch", expected,
+                 &[("multi objs", iter_multi_objs(&multi_objs_from_ids(ids)))]);
+}
+
+/// Check that the payload enum and the equivalent trait objects agree.
+pub fn check_payloads<E: PayloadEnum<N>, const N: usize>(ids: &[i32], data: &[u32]) {
+  let expected = expect_i32("dispatch payload", payloads::<E, N>(ids, data).iter()
+      .map(|(id, payload)| payload_work(*id as u32, payload) as i64).sum());
+  check_variants("dispatch payload", expected,
+                 &[("enum", iter_enum(&payload_enums::<E, N>(ids, data))),
+                   ("objs", iter_objs(&payload_objs::<E, N>(ids, data)))]);
+}
+
+/// Check the variants over generated data.
+pub fn verify(seed: u64, size: usize) {
+  check_digits(&crate::random_vec_in(0..10, size, seed));
+  for classes in [1, 3, 10, 50] {
+    for layout in [Layout::Random, Layout::SortedBlocks, Layout::TickTock,
+                   Layout::ShuffledWindow { copies: 10 }, Layout::Markov { stay: 0.9 }] {
+      check_classes(&ids_from_layout(layout, classes, size, seed));
+    }
+  }
+  let data = crate::random_vec_of::<u32>(16 * size, seed);
+  check_payloads::<Payload3<1>, 1>(&crate::random_vec_in(0..3, size, seed), &data);
+  check_payloads::<Payload16<4>, 4>(&crate::random_vec_in(0..16, size, seed), &data);
+  check_payloads::<Payload64<64>, 64>(&crate::random_vec_in(0..64, size, seed), &data);
+  check_payloads::<Payload256<16>, 16>(&crate::random_vec_in(0..256, size, seed), &data);
+}
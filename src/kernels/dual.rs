@@ -0,0 +1,41 @@
+// Copyright by Owen O'Malley 2024
+
+use std::ops::Range;
+use crate::verify::{check_variants, expect_i32};
+
+// Compare zip and for loops. The functions multiply the values
+// and sum the products.
+
+/// The range of the values, which is small enough that the sum of the
+/// squares of 10,000 values doesn't overflow.
+pub const RANGE: Range<i32> = -256..256;
+
+/// Use iteration, zip, map, and sum.
+pub fn dual_via_zip(left: &[i32], right: &[i32]) -> i32 {
+  left.iter().zip(right.iter())
+      .map(|(l,r)| l * r)
+      .sum()
+}
+
+/// Use the manual for loop.
+pub fn dual_via_for(left: &[i32], right: &[i32]) -> i32 {
+  let mut result = 0;
+  for i in 0..left.len() {
+    result += left[i] * right[i];
+  }
+  result
+}
+
+/// Check that both variants compute the same sum of products.
+pub fn check(left: &[i32], right: &[i32]) {
+  let expected = expect_i32("dual", left.iter().zip(right.iter())
+      .map(|(l, r)| *l as i64 * *r as i64).sum());
+  check_variants("dual", expected, &[("zip", dual_via_zip(left, right)),
+    ("for", dual_via_for(left, right))]);
+}
+
+/// Check the variants over generated data.
+pub fn verify(seed: u64, size: usize) {
+  let array = crate::random_vec_in(RANGE, size, seed);
+  check(&array, &array);
+}
@@ -0,0 +1,50 @@
+// Copyright by Owen O'Malley 2024
+
+use crate::verify::{check_variants, expect_i32};
+
+// This group tests the relative performance of loops,
+// iterators, arrays, and vectors.
+
+// All of the functions compute the sum of the data.
+
+/// Use a for loop via indexing
+#[allow(clippy::needless_range_loop)]
+pub fn sum_via_index(data: &[i32]) -> i32 {
+  let mut sum = 0;
+  for i in 0..data.len() {
+    sum += data[i];
+  }
+  sum
+}
+
+/// A for loop over the data directly
+pub fn sum_via_for(data: &[i32]) -> i32 {
+  let mut sum = 0;
+  for i in data {
+    sum += i;
+  }
+  sum
+}
+
+/// iterate and use sum.
+pub fn sum_via_sum(data: &[i32]) -> i32 {
+  data.iter().sum()
+}
+
+/// Iterate and use fold.
+#[allow(clippy::unnecessary_fold)]
+pub fn sum_via_fold(data: &[i32]) -> i32 {
+  data.iter().fold(0, |acc, i| acc + i)
+}
+
+/// Check that all of the variants compute the same sum.
+pub fn check(data: &[i32]) {
+  let expected = expect_i32("element", data.iter().map(|x| *x as i64).sum());
+  check_variants("element", expected, &[("index", sum_via_index(data)),
+    ("for", sum_via_for(data)), ("sum", sum_via_sum(data)), ("fold", sum_via_fold(data))]);
+}
+
+/// Check the variants over generated data.
+pub fn verify(seed: u64, size: usize) {
+  check(&crate::random_vec_in(-100_000..100_000, size, seed));
+}
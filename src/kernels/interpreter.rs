@@ -0,0 +1,257 @@
+// Copyright by Owen O'Malley 2024
+
+// A mini bytecode interpreter for the dispatch category, which turns the
+// sequences of class ids from the layouts into programs of opcode bytes,
+// like the interpreters and codecs that dispatch on a byte instead of a
+// trait object. Each instruction is an opcode byte and an operand byte,
+// and the execution loops differ only in how they dispatch:
+//
+// * run_match: a match on the opcode byte
+// * run_table: a table of functions indexed by the opcode byte
+// * run_vtables: instructions decoded ahead of time into a reference to
+//     a hand-built vtable, which is a struct of function pointers
+// * run_closures: instructions compiled ahead of time into closures that
+//     capture their operands, which is closure threaded code
+
+use crate::patterns::Layout;
+use crate::verify::check_variants;
+use super::dispatch::ids_from_layout;
+
+/// The number of registers beside the accumulator.
+pub const REGISTERS: usize = 4;
+
+/// The state of the machine that the programs run on.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Machine {
+  pub acc: i32,
+  pub regs: [i32; REGISTERS],
+}
+
+impl Machine {
+  /// Combine the accumulator and the registers into the program's
+  /// result.
+  pub fn result(&self) -> i32 {
+    self.regs.iter().fold(self.acc, |acc, r| acc.wrapping_add(*r))
+  }
+
+  fn reg(&mut self, arg: u8) -> &mut i32 {
+    &mut self.regs[arg as usize % REGISTERS]
+  }
+}
+
+pub type OpFn = fn(&mut Machine, u8);
+
+/// An instruction that was compiled to a closure with its operand.
+pub type OpClosure = Box<dyn Fn(&mut Machine)>;
+
+fn add(m: &mut Machine, arg: u8) {
+  m.acc = m.acc.wrapping_add(arg as i32);
+}
+
+fn sub(m: &mut Machine, arg: u8) {
+  m.acc = m.acc.wrapping_sub(arg as i32);
+}
+
+fn mul(m: &mut Machine, arg: u8) {
+  m.acc = m.acc.wrapping_mul(arg as i32 | 1);
+}
+
+fn xor(m: &mut Machine, arg: u8) {
+  m.acc ^= arg as i32;
+}
+
+fn rotate(m: &mut Machine, arg: u8) {
+  m.acc = m.acc.rotate_left(arg as u32 % 32);
+}
+
+fn neg(m: &mut Machine, _arg: u8) {
+  m.acc = m.acc.wrapping_neg();
+}
+
+fn load(m: &mut Machine, arg: u8) {
+  m.acc = *m.reg(arg);
+}
+
+fn store(m: &mut Machine, arg: u8) {
+  *m.reg(arg) = m.acc;
+}
+
+fn add_reg(m: &mut Machine, arg: u8) {
+  m.acc = m.acc.wrapping_add(*m.reg(arg));
+}
+
+fn max_reg(m: &mut Machine, arg: u8) {
+  m.acc = m.acc.max(*m.reg(arg));
+}
+
+fn swap(m: &mut Machine, arg: u8) {
+  let mut acc = m.acc;
+  std::mem::swap(&mut acc, m.reg(arg));
+  m.acc = acc;
+}
+
+fn inc_reg(m: &mut Machine, arg: u8) {
+  let reg = m.reg(arg);
+  *reg = reg.wrapping_add(1);
+}
+
+/// A hand-built vtable for an opcode.
+pub struct OpVTable {
+  pub name: &'static str,
+  pub execute: OpFn,
+}
+
+/// An instruction that was decoded to its opcode's vtable.
+pub struct VInstr {
+  pub vtable: &'static OpVTable,
+  pub arg: u8,
+}
+
+macro_rules! define_opcodes {
+    ( $($op:literal => $name:ident),* ) => {
+
+      /// The number of opcodes, which are 0 to OPCODES - 1.
+      pub const OPCODES: usize = [$($op),*].len();
+
+      /// The functions for the opcodes indexed by opcode.
+      pub const OP_FUNCS: [OpFn; OPCODES] = [$($name),*];
+
+      /// The vtables for the opcodes indexed by opcode.
+      pub static VTABLES: [OpVTable; OPCODES] =
+          [$(OpVTable { name: stringify!($name), execute: $name }),*];
+
+      /// Execute one instruction with a match on the opcode.
+      #[inline(always)]
+      fn execute(m: &mut Machine, op: u8, arg: u8) {
+        match op {
+          $($op => $name(m, arg),)*
+          _ => unreachable!("The programs are validated"),
+        }
+      }
+
+      /// Create the closure that executes one instruction.
+      fn closure(op: u8, arg: u8) -> OpClosure {
+        match op {
+          $($op => Box::new(move |m: &mut Machine| $name(m, arg)),)*
+          _ => unreachable!("The programs are validated"),
+        }
+      }
+    }
+}
+
+define_opcodes!(0 => add, 1 => sub, 2 => mul, 3 => xor, 4 => rotate, 5 => neg, 6 => load,
+                7 => store, 8 => add_reg, 9 => max_reg, 10 => swap, 11 => inc_reg);
+
+/// A bytecode program whose instructions have all been checked, so the
+/// execution loops don't need to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+  code: Vec<u8>,
+}
+
+impl Program {
+  /// Check that the code is whole instructions with valid opcodes. It is
+  /// checked once here, so the execution loops don't check it.
+  pub fn new(code: Vec<u8>) -> Result<Self, String> {
+    if !code.len().is_multiple_of(2) {
+      return Err(format!("The program has a partial instruction at byte {}", code.len() - 1));
+    }
+    match code.iter().step_by(2).position(|op| *op as usize >= OPCODES) {
+      Some(i) => Err(format!("Bad opcode {} at instruction {i}", code[2 * i])),
+      None => Ok(Program { code }),
+    }
+  }
+
+  pub fn code(&self) -> &[u8] {
+    &self.code
+  }
+
+  /// The number of instructions.
+  pub fn len(&self) -> usize {
+    self.code.len() / 2
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.code.is_empty()
+  }
+
+  /// The opcode and operand of each instruction.
+  fn instructions(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+    self.code.chunks_exact(2).map(|instr| (instr[0], instr[1]))
+  }
+}
+
+/// Generate a program of size instructions whose opcodes follow the
+/// layout of the given number of opcodes, with random operands.
+pub fn program_from_layout(layout: Layout, opcodes: usize, size: usize, seed: u64) -> Program {
+  assert!(opcodes <= OPCODES, "There are only {OPCODES} opcodes, not {opcodes}");
+  // Use a different stream than the opcodes so that the operands don't
+  // depend on them.
+  let args = crate::random_vec_of::<u8>(size, seed.wrapping_add(1));
+  Program { code: ids_from_layout(layout, opcodes, size, seed).iter().zip(args)
+      .flat_map(|(op, arg)| [*op as u8, arg]).collect() }
+}
+
+/// Run the program with a match on each opcode byte.
+pub fn run_match(program: &Program) -> i32 {
+  let mut m = Machine::default();
+  for (op, arg) in program.instructions() {
+    execute(&mut m, op, arg);
+  }
+  m.result()
+}
+
+/// Run the program by calling through a table of functions.
+pub fn run_table(program: &Program, table: &[OpFn; OPCODES]) -> i32 {
+  let mut m = Machine::default();
+  for (op, arg) in program.instructions() {
+    table[op as usize](&mut m, arg);
+  }
+  m.result()
+}
+
+/// Decode the program into the vtables of its opcodes.
+pub fn decode_vtables(program: &Program) -> Vec<VInstr> {
+  program.instructions().map(|(op, arg)| VInstr { vtable: &VTABLES[op as usize], arg }).collect()
+}
+
+/// Run the decoded program by calling through each instruction's vtable.
+pub fn run_vtables(program: &[VInstr]) -> i32 {
+  let mut m = Machine::default();
+  for instr in program {
+    (instr.vtable.execute)(&mut m, instr.arg);
+  }
+  m.result()
+}
+
+/// Compile the program into a closure per instruction.
+pub fn compile_closures(program: &Program) -> Vec<OpClosure> {
+  program.instructions().map(|(op, arg)| closure(op, arg)).collect()
+}
+
+/// Run the compiled program by calling each instruction's closure.
+pub fn run_closures(program: &[OpClosure]) -> i32 {
+  let mut m = Machine::default();
+  for instr in program {
+    instr(&mut m);
+  }
+  m.result()
+}
+
+/// Check that the execution loops agree on the program.
+pub fn check_program(program: &Program) {
+  check_variants("dispatch interpreter", run_match(program),
+                 &[("table", run_table(program, &OP_FUNCS)),
+                   ("vtable", run_vtables(&decode_vtables(program))),
+                   ("closures", run_closures(&compile_closures(program)))]);
+}
+
+/// Check the execution loops over generated programs.
+pub fn verify(seed: u64, size: usize) {
+  for opcodes in [1, 3, OPCODES] {
+    for layout in [Layout::Random, Layout::SortedBlocks, Layout::TickTock,
+                   Layout::ShuffledWindow { copies: 10 }, Layout::Markov { stay: 0.9 }] {
+      check_program(&program_from_layout(layout, opcodes, size, seed));
+    }
+  }
+}
@@ -0,0 +1,14 @@
+// Copyright by Owen O'Malley 2024
+
+// The kernels that the benchmarks time. Each module holds the variants
+// for one category, which all compute the same result, along with the
+// checks that they agree.
+
+pub mod branching;
+pub mod dispatch;
+pub mod dual;
+pub mod elements;
+pub mod interpreter;
+pub mod option;
+pub mod sliding;
+pub mod tail;
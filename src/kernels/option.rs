@@ -0,0 +1,80 @@
+// Copyright by Owen O'Malley 2024
+
+use crate::verify::check_variants;
+
+// Compare the various ways to handle Option values.
+
+// These functions take a slice of Option<String> and compute the
+// sum of the lengths of the String values.
+
+/// Iterate through the data, apply filter_map to drop the None values
+/// and keep the string lengths. Finally sum is applied.
+#[allow(clippy::bind_instead_of_map)]
+pub fn filter_map(data: &[Option<String>]) -> usize {
+  data.iter().filter_map(|x|
+     x.as_ref().and_then(|s| Some(s.len()))).sum()
+}
+
+/// A for loop and if to test whether it is a String.
+pub fn for_if(data: &[Option<String>]) -> usize {
+  let mut result = 0;
+  for x in data {
+    if x.is_some() {
+      result += x.as_ref().unwrap().len();
+    }
+  }
+  result
+}
+
+/// As above, but with if let to find the Strings.
+#[allow(clippy::manual_flatten)]
+pub fn for_if_let(data: &[Option<String>]) -> usize {
+  let mut result = 0;
+  for x in data {
+    if let Some(s) = x {
+      result += s.len();
+    }
+  }
+  result
+}
+
+/// A for loop with match to find strings.
+#[allow(clippy::single_match)]
+pub fn for_match(data: &[Option<String>]) -> usize {
+  let mut result = 0;
+  for x in data {
+    match x {
+      Some(s) => result += s.len(),
+      _ => {},
+    }
+  }
+  result
+}
+
+/// Iteration, match to find the Strings, and sum.
+pub fn match_sum(data: &[Option<String>]) -> usize {
+  data.iter().map(|x| match x {
+    Some(s) => s.len(),
+    _ => 0,
+  }).sum()
+}
+
+/// Uses Option.map_or, which provides a default value if the input is None
+/// or applies a lambda when it has a value.
+pub fn map_or(data: &[Option<String>]) -> usize {
+  data.iter().map(|x| 
+    x.as_ref().map_or(0, |s| s.len())).sum()
+}
+
+/// Check that all of the variants compute the same total length.
+pub fn check(data: &[Option<String>]) {
+  let expected = data.iter().flatten().map(String::len).sum();
+  check_variants("option", expected, &[("for if", for_if(data)),
+    ("for if_let", for_if_let(data)), ("for match", for_match(data)),
+    ("filter_map", filter_map(data)), ("match sum", match_sum(data)), ("map_or", map_or(data))]);
+}
+
+/// Check the variants over generated data.
+pub fn verify(seed: u64, size: usize) {
+  check(&crate::random_string_vec(size, seed));
+}
@@ -0,0 +1,36 @@
+// Copyright by Owen O'Malley 2024
+
+use crate::verify::{check_variants, expect_i32};
+
+// These functions use a sliding window and compute the 
+// diference between adjacent elements and then sum the differences.
+
+/// Use high level window function, map, and sum.
+pub fn compute_window(data: &[i32]) -> i32 {
+  data.windows(2).map(|x| x[0] - x[1]).sum()
+}
+
+/// Use the equivalent for loop.
+pub fn compute_for(data: &[i32]) -> i32 {
+  let mut result = 0;
+  for i in 0..data.len().saturating_sub(1) {
+    result += data[i] - data[i+1];
+  }
+  result
+}
+
+/// Check that both variants compute the same sum of differences.
+pub fn check(data: &[i32]) {
+  // The differences telescope, so the sum is just the first minus the last.
+  let expected = match data {
+    [first, .., last] => expect_i32("sliding", *first as i64 - *last as i64),
+    _ => 0,
+  };
+  check_variants("sliding", expected, &[("window", compute_window(data)),
+    ("for", compute_for(data))]);
+}
+
+/// Check the variants over generated data.
+pub fn verify(seed: u64, size: usize) {
+  check(&crate::random_vec_in(-100_000..100_000, size, seed));
+}
@@ -0,0 +1,97 @@
+// Copyright by Owen O'Malley 2024
+
+use crate::verify::{check_variants, expect_i32};
+use tailcall::tailcall;
+
+// Study how the compiler deals with tail recursion with
+// functions that compute the sum of the array.
+
+/// Match against the slices and recurse with a final addition.
+// tailcall rejects this because of the final addition.
+pub fn sum_via_match(data: &[i32]) -> i32 {
+  match data {
+    [] => 0,
+    [val] => *val,
+    [val, ..] => val + sum_via_match(&data[1..]),
+  }
+}
+
+/// Match against the slices with an accumulator to avoid the final
+/// addition.
+#[tailcall]
+pub fn sum_via_match_accum(data: &[i32], previous: i32) -> i32 {
+  match data {
+    [] => previous,
+    [val] => *val + previous,
+    [val, ..] => sum_via_match_accum(&data[1..], val + previous),
+  }
+}
+
+/// Same as the previous, but with only two match branches. The
+/// middle pattern is handled as a special case of the final 
+/// pattern. This is significantly faster.
+#[tailcall]
+pub fn sum_via_match2_accum(data: &[i32], previous: i32) -> i32 {
+  match data {
+    [] => previous,
+    [val, ..] => sum_via_match2_accum(&data[1..], val + previous),
+  }
+}
+
+/// Match on the length of the data rather than the front of the slice.
+#[tailcall]
+pub fn sum_via_len_match_accum(data: &[i32], previous: i32) -> i32 {
+  match data.len() {
+    0 => previous,
+    1 => data[0] + previous,
+    _ => sum_via_len_match_accum(&data[1..], data[0] + previous),
+  }
+}
+
+/// Take the manual approach of passing in the start index and an
+/// accumulator.
+#[tailcall]
+pub fn sum_via_if_idx_accum(data: &[i32], i: usize, previous: i32) -> i32 {
+  if i < data.len() {
+    sum_via_if_idx_accum(data, i + 1, data[i] + previous)
+  } else {
+    previous
+  }
+}
+
+/// Use an if instead of the match and use an accumulator.
+#[tailcall]
+pub fn sum_via_if_accum(data: &[i32], previous: i32) -> i32 {
+  if data.is_empty() {
+    previous
+  } else {
+    sum_via_if_accum(&data[1..], data[0] + previous)
+  }
+}
+
+/// Finally, use an if branch without the accumulator.
+// tailcall rejects this.
+pub fn sum_via_if(data: &[i32]) -> i32 {
+  if data.is_empty() {
+    0
+  } else {
+    data[0] + sum_via_if(&data[1..])
+  }
+}
+
+/// Check that all of the variants compute the same sum.
+pub fn check(data: &[i32]) {
+  let expected = expect_i32("tail", data.iter().map(|x| *x as i64).sum());
+  check_variants("tail", expected, &[("match", sum_via_match(data)),
+    ("match accum", sum_via_match_accum(data, 0)),
+    ("match2 accum", sum_via_match2_accum(data, 0)),
+    ("len match accum", sum_via_len_match_accum(data, 0)),
+    ("if idx accum", sum_via_if_idx_accum(data, 0, 0)),
+    ("if accum", sum_via_if_accum(data, 0)),
+    ("if", sum_via_if(data))]);
+}
+
+/// Check the variants over generated data.
+pub fn verify(seed: u64, size: usize) {
+  check(&crate::random_vec_in(-100_000..100_000, size, seed));
+}
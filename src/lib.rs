@@ -1,23 +1,259 @@
 // Copyright by Owen O'Malley 2024
 
+use std::env;
+use std::fmt;
 use std::ops::Range;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
 use rand_chacha::ChaChaRng;
 use rand_chacha::rand_core::SeedableRng;
 use rand::Rng;
+use rand::distributions::{Distribution, Standard};
+use rand::distributions::uniform::SampleUniform;
+use rand_distr::{Normal, Zipf};
+
+pub mod asm;
+pub mod cache;
+pub mod compare;
+pub mod cpp;
+pub mod instructions;
+pub mod kernels;
+pub mod machine;
+pub mod noise;
+pub mod patterns;
+pub mod perf;
+pub mod predictor;
+pub mod report;
+pub mod results;
+pub mod seeds;
+pub mod select;
+pub mod verify;
+
+/// The directory where Criterion writes its results. Like Criterion,
+/// use $CRITERION_HOME, $CARGO_TARGET_DIR/criterion, or target/criterion.
+pub fn criterion_home() -> PathBuf {
+  if let Some(home) = env::var_os("CRITERION_HOME") {
+    PathBuf::from(home)
+  } else if let Some(target) = env::var_os("CARGO_TARGET_DIR") {
+    PathBuf::from(target).join("criterion")
+  } else {
+    PathBuf::from("target/criterion")
+  }
+}
+
+/// Build the bench binary in the bench profile and return its path.
+pub fn bench_binary() -> Result<PathBuf, String> {
+  let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+  let output = Command::new(cargo)
+      .args(["bench", "--no-run", "--bench", "bench", "--message-format=json"])
+      .stderr(Stdio::inherit())
+      .output()
+      .map_err(|err| format!("Can't run cargo: {err}"))?;
+  if !output.status.success() {
+    return Err("Can't build the bench binary".to_string());
+  }
+  String::from_utf8_lossy(&output.stdout).lines()
+      .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+      .filter(|message| message["target"]["name"] == "bench")
+      .find_map(|message| message["executable"].as_str().map(PathBuf::from))
+      .ok_or_else(|| "Cargo didn't report the bench binary".to_string())
+}
+
+// All of the generators use ChaChaRng with a fixed seed, so that a
+// given seed always produces the same data on every platform.
+
+/// The seed for the benchmarks' data, which is $RUST_BENCH_SEED or 0.
+/// The seed sweep runs the benchmarks with several seeds to see how much
+/// the results depend on the particular random data.
+pub fn seed_from_env() -> Result<u64, String> {
+  match env::var("RUST_BENCH_SEED") {
+    Ok(seed) if !seed.trim().is_empty() => seed.trim().parse()
+        .map_err(|_| format!("Bad RUST_BENCH_SEED {seed:?}, which must be a decimal u64")),
+    _ => Ok(0),
+  }
+}
+
+/// The seed from seed_from_env, which panics on a bad seed rather than
+/// silently generating the data for another one. The harnesses check
+/// the seed before they start.
+pub fn data_seed() -> u64 {
+  seed_from_env().unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Create the random number generator that all of the generators use.
+pub fn seeded_rng(seed: u64) -> ChaChaRng {
+  SeedableRng::seed_from_u64(seed)
+}
 
 pub fn random_array<const SIZE:usize>(range: Range<i32>, seed: u64) -> [i32; SIZE] {
-  let mut rng: ChaChaRng = SeedableRng::seed_from_u64(seed);
+  random_array_in(range, seed)
+}
+
+/// Generate a fixed sized array of values drawn uniformly from the range.
+pub fn random_array_in<T, const SIZE:usize>(range: Range<T>, seed: u64) -> [T; SIZE]
+  where T: SampleUniform + PartialOrd + Clone {
+  let mut rng = seeded_rng(seed);
   core::array::from_fn(|_| rng.gen_range(range.clone()))
 }
 
+/// Generate a vector of values drawn uniformly from the range.
+pub fn random_vec_in<T>(range: Range<T>, size: usize, seed: u64) -> Vec<T>
+  where T: SampleUniform + PartialOrd + Clone {
+  let mut rng = seeded_rng(seed);
+  (0..size).map(|_| rng.gen_range(range.clone())).collect()
+}
+
+/// Generate a fixed sized array of values drawn from the whole domain
+/// of the type. This covers the types without a natural range, such
+/// as bool, and the floats, which are in [0, 1).
+pub fn random_array_of<T, const SIZE:usize>(seed: u64) -> [T; SIZE]
+  where Standard: Distribution<T> {
+  let mut rng = seeded_rng(seed);
+  core::array::from_fn(|_| rng.gen())
+}
+
+/// Generate a vector of values drawn from the whole domain of the type.
+pub fn random_vec_of<T>(size: usize, seed: u64) -> Vec<T>
+  where Standard: Distribution<T> {
+  let mut rng = seeded_rng(seed);
+  (0..size).map(|_| rng.gen()).collect()
+}
+
+/// Generate a random string or None with equal probability.
+fn random_string(rng: &mut ChaChaRng) -> Option<String> {
+  if rng.gen_bool(0.5) {
+    None
+  } else {
+    let len: usize = rng.gen_range(0..20);
+    Some(format!("{:1$}", "", len))
+  }
+}
+
 pub fn random_string_array<const SIZE:usize>(seed: u64) -> [Option<String>; SIZE] {
-  let mut rng: ChaChaRng = SeedableRng::seed_from_u64(seed);
-  core::array::from_fn(|_| {
-    if rng.gen_bool(0.5) {
-      None
-    } else {
-      let len: usize = rng.gen_range(0..20);
-      Some(format!("{:1$}", "", len))
+  let mut rng = seeded_rng(seed);
+  core::array::from_fn(|_| random_string(&mut rng))
+}
+
+/// The vector version of random_string_array.
+pub fn random_string_vec(size: usize, seed: u64) -> Vec<Option<String>> {
+  let mut rng = seeded_rng(seed);
+  (0..size).map(|_| random_string(&mut rng)).collect()
+}
+
+// The generators below shape the data instead of drawing it uniformly,
+// since branch prediction depends heavily on the pattern of the input.
+
+/// Generate values from a Zipfian distribution, where the start of the
+/// range is the most common value and larger exponents are more skewed.
+pub fn zipf_vec(range: Range<i32>, exponent: f64, size: usize, seed: u64) -> Vec<i32> {
+  assert!(!range.is_empty(), "zipf needs a non-empty range, not {range:?}");
+  let mut rng = seeded_rng(seed);
+  let zipf = Zipf::new(range.len() as u64, exponent)
+      .unwrap_or_else(|_| panic!("bad zipf exponent {exponent}"));
+  (0..size).map(|_| range.start + rng.sample(zipf) as i32 - 1).collect()
+}
+
+/// Generate values from a normal distribution centered in the range.
+/// Values that fall outside of the range are clamped to it.
+pub fn normal_vec(range: Range<i32>, std_dev: f64, size: usize, seed: u64) -> Vec<i32> {
+  let mut rng = seeded_rng(seed);
+  let mean = (range.start as f64 + range.end as f64 - 1.0) / 2.0;
+  let normal = Normal::new(mean, std_dev).expect("bad standard deviation");
+  (0..size).map(|_| (rng.sample(normal).round() as i32).clamp(range.start, range.end - 1))
+      .collect()
+}
+
+/// Generate uniform values in ascending order.
+pub fn sorted_vec(range: Range<i32>, size: usize, seed: u64) -> Vec<i32> {
+  let mut result = random_vec_in(range, size, seed);
+  result.sort_unstable();
+  result
+}
+
+/// Generate uniform values in descending order.
+pub fn reverse_sorted_vec(range: Range<i32>, size: usize, seed: u64) -> Vec<i32> {
+  let mut result = sorted_vec(range, size, seed);
+  result.reverse();
+  result
+}
+
+/// Generate sorted values and then swap percent% of them with other
+/// random positions.
+pub fn nearly_sorted_vec(range: Range<i32>, percent: usize, size: usize,
+                         seed: u64) -> Vec<i32> {
+  let mut result = sorted_vec(range, size, seed);
+  // Use a different stream than the values so that the swaps don't
+  // depend on the values.
+  let mut rng = seeded_rng(seed.wrapping_add(1));
+  if size > 0 {
+    for _ in 0..(size * percent / 100) {
+      result.swap(rng.gen_range(0..size), rng.gen_range(0..size));
+    }
+  }
+  result
+}
+
+/// Generate runs of run_length copies of a uniform random value.
+pub fn runs_vec(range: Range<i32>, run_length: usize, size: usize, seed: u64) -> Vec<i32> {
+  assert!(run_length > 0, "run length must be positive");
+  let mut rng = seeded_rng(seed);
+  let mut value = range.start;
+  (0..size).map(|i| {
+    if i % run_length == 0 {
+      value = rng.gen_range(range.clone());
+    }
+    value
+  }).collect()
+}
+
+/// The shapes of data that the generators can produce, so that the
+/// benchmarks can sweep over them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+  Uniform,
+  Zipf { exponent: f64 },
+  /// The standard deviation is spread times the width of the range, so
+  /// that the shape is the same for any range.
+  Normal { spread: f64 },
+  Sorted,
+  ReverseSorted,
+  NearlySorted { percent: usize },
+  Runs { length: usize },
+}
+
+impl Shape {
+  /// A representative set of shapes for benchmarking.
+  pub const ALL: [Shape; 7] = [Shape::Uniform, Shape::Zipf { exponent: 1.0 },
+    Shape::Normal { spread: 0.15 }, Shape::Sorted, Shape::ReverseSorted,
+    Shape::NearlySorted { percent: 5 }, Shape::Runs { length: 16 }];
+
+  /// Generate size values from the range with this shape.
+  pub fn generate(&self, range: Range<i32>, size: usize, seed: u64) -> Vec<i32> {
+    match *self {
+      Shape::Uniform => random_vec_in(range, size, seed),
+      Shape::Zipf { exponent } => zipf_vec(range, exponent, size, seed),
+      Shape::Normal { spread } => {
+        let std_dev = spread * range.len() as f64;
+        normal_vec(range, std_dev, size, seed)
+      }
+      Shape::Sorted => sorted_vec(range, size, seed),
+      Shape::ReverseSorted => reverse_sorted_vec(range, size, seed),
+      Shape::NearlySorted { percent } => nearly_sorted_vec(range, percent, size, seed),
+      Shape::Runs { length } => runs_vec(range, length, size, seed),
+    }
+  }
+}
+
+impl fmt::Display for Shape {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Shape::Uniform => write!(f, "uniform"),
+      Shape::Zipf { exponent } => write!(f, "zipf {exponent}"),
+      Shape::Normal { spread } => write!(f, "normal {spread}"),
+      Shape::Sorted => write!(f, "sorted"),
+      Shape::ReverseSorted => write!(f, "reverse sorted"),
+      Shape::NearlySorted { percent } => write!(f, "nearly sorted {percent}%"),
+      Shape::Runs { length } => write!(f, "runs {length}"),
     }
-  })
-}
\ No newline at end of file
+  }
+}
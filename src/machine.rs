@@ -0,0 +1,157 @@
+// Copyright by Owen O'Malley 2024
+
+// Record the machine and toolchain that ran the benchmarks, so that
+// results from different machines can be compared safely. Most of the
+// details come from /proc and /sys, so they are empty on other
+// platforms.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use crate::cache::{self, Cache};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fingerprint {
+  pub cpu_model: String,
+  pub cpu_vendor: String,
+  /// The family, model, and stepping that identify the microarchitecture.
+  pub cpu_family: String,
+  pub cpu_model_number: String,
+  pub cpu_stepping: String,
+  /// The CPU's feature flags, such as avx2 or asimd.
+  pub cpu_flags: Vec<String>,
+  pub logical_cores: usize,
+  pub physical_cores: usize,
+  pub caches: Vec<Cache>,
+  pub os: String,
+  pub kernel: String,
+  pub governor: Option<String>,
+  pub turbo: Option<bool>,
+  pub rustc: String,
+  pub target: String,
+  pub target_features: Vec<String>,
+  pub profile: String,
+  pub opt_level: String,
+  /// The extra flags for rustc, such as -C target-cpu=native.
+  #[serde(default)]
+  pub rustflags: Vec<String>,
+  /// Either "unwind" or "abort".
+  #[serde(default)]
+  pub panic: String,
+}
+
+/// Get the value of the first line in /proc/cpuinfo with the given key.
+fn cpuinfo_value(cpuinfo: &str, key: &str) -> Option<String> {
+  cpuinfo.lines()
+      .filter_map(|line| line.split_once(':'))
+      .find(|(k, _)| k.trim() == key)
+      .map(|(_, v)| v.trim().to_string())
+}
+
+/// Count the distinct (physical id, core id) pairs in /proc/cpuinfo.
+fn count_physical_cores(cpuinfo: &str) -> usize {
+  let mut cores = HashSet::new();
+  for processor in cpuinfo.split("\n\n") {
+    if let (Some(package), Some(core)) = (cpuinfo_value(processor, "physical id"),
+                                          cpuinfo_value(processor, "core id")) {
+      cores.insert((package, core));
+    }
+  }
+  cores.len()
+}
+
+pub(crate) fn read_trimmed(path: &str) -> Option<String> {
+  fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Read the turbo state from the Intel or the generic cpufreq driver.
+pub(crate) fn read_turbo() -> Option<bool> {
+  if let Some(no_turbo) = read_trimmed("/sys/devices/system/cpu/intel_pstate/no_turbo") {
+    return Some(no_turbo == "0");
+  }
+  read_trimmed("/sys/devices/system/cpu/cpufreq/boost").map(|boost| boost == "1")
+}
+
+/// Run a command and return its trimmed output, if it succeeds.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+  let output = Command::new(program).args(args).output().ok()?;
+  if output.status.success() {
+    String::from_utf8(output.stdout).ok().map(|s| s.trim().to_string())
+  } else {
+    None
+  }
+}
+
+/// The file where the bench binary records the machine, next to
+/// Criterion's results, so that exporting them later describes the
+/// machine and build that ran them.
+pub fn recorded_path(criterion_home: &Path) -> PathBuf {
+  criterion_home.join("machine.json")
+}
+
+fn split_list(text: &str, separator: char) -> Vec<String> {
+  text.split(separator).map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect()
+}
+
+impl Fingerprint {
+  /// Collect the fingerprint of the current machine.
+  pub fn collect() -> Self {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    let value = |key: &str| cpuinfo_value(&cpuinfo, key).unwrap_or_default();
+    let logical_cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let physical_cores = count_physical_cores(&cpuinfo);
+    // Linux on ARM calls the flags Features and doesn't name the CPU.
+    let flags = cpuinfo_value(&cpuinfo, "flags")
+        .or_else(|| cpuinfo_value(&cpuinfo, "Features"))
+        .unwrap_or_default();
+    let cpu_model = cpuinfo_value(&cpuinfo, "model name")
+        .or_else(|| command_output("sysctl", &["-n", "machdep.cpu.brand_string"]))
+        .unwrap_or_default();
+    Fingerprint {
+      cpu_model,
+      cpu_vendor: value("vendor_id"),
+      cpu_family: value("cpu family"),
+      cpu_model_number: value("model"),
+      cpu_stepping: value("stepping"),
+      cpu_flags: split_list(&flags, ' '),
+      logical_cores,
+      physical_cores: if physical_cores == 0 { logical_cores } else { physical_cores },
+      caches: cache::caches(),
+      os: std::env::consts::OS.to_string(),
+      kernel: read_trimmed("/proc/sys/kernel/osrelease")
+          .or_else(|| command_output("uname", &["-r"]))
+          .unwrap_or_default(),
+      governor: read_trimmed("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor"),
+      turbo: read_turbo(),
+      rustc: env!("RUST_BENCH_RUSTC").to_string(),
+      target: env!("RUST_BENCH_TARGET").to_string(),
+      target_features: split_list(env!("RUST_BENCH_TARGET_FEATURES"), ','),
+      profile: env!("RUST_BENCH_PROFILE").to_string(),
+      opt_level: env!("RUST_BENCH_OPT_LEVEL").to_string(),
+      rustflags: split_list(env!("RUST_BENCH_RUSTFLAGS"), '\x1f'),
+      panic: env!("RUST_BENCH_PANIC").to_string(),
+    }
+  }
+
+  /// A one line summary, like the one at the top of the README.
+  pub fn summary(&self) -> String {
+    format!("{} ({} cores), {} {}, {}", self.cpu_model, self.logical_cores, self.os,
+            self.kernel, self.rustc)
+  }
+
+  /// Write the fingerprint as JSON.
+  pub fn write(&self, path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(self)?)
+  }
+
+  /// Read a fingerprint that was written by write.
+  pub fn read(path: &Path) -> io::Result<Self> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+  }
+}
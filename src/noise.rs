@@ -0,0 +1,261 @@
+// Copyright by Owen O'Malley 2024
+
+// Reduce and detect the noise from the rest of the machine. Benchmarks
+// on a multi-process OS have substantial jitter, so a run can pin the
+// benchmark thread to one core and raise its priority, and before
+// timing it checks the frequency scaling, turbo, and the load on the
+// other cores. The settings come from the environment:
+//
+//   RUST_BENCH_PIN=N     pin the benchmark thread to CPU N
+//   RUST_BENCH_NICE=N    set the thread's nice value, such as -10
+//   RUST_BENCH_STRICT=1  refuse to run when the machine is noisy
+//   RUST_BENCH_NO_LOAD=1 skip watching the load, which takes 250ms
+//
+// The flags are off when they are unset, empty, or 0. Every decision and
+// check is recorded in the run, including a setting that doesn't parse,
+// which is a noisy check rather than silently off.
+
+use std::env;
+use std::fs;
+use std::thread;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::machine::{read_trimmed, read_turbo};
+
+/// The fraction of a CPU's time that another process may use before it
+/// counts as noise.
+pub const BUSY_LIMIT: f64 = 0.1;
+
+/// How long to watch the other CPUs for.
+const SAMPLE_TIME: Duration = Duration::from_millis(250);
+
+/// One check of the machine's state.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Check {
+  pub name: String,
+  pub value: String,
+  /// Whether the state adds noise to the timings.
+  pub noisy: bool,
+}
+
+/// The noise controls that a run used and the state of the machine.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoiseReport {
+  pub pinned_cpu: Option<usize>,
+  pub nice: Option<i32>,
+  pub strict: bool,
+  pub checks: Vec<Check>,
+}
+
+impl NoiseReport {
+  pub fn is_noisy(&self) -> bool {
+    self.checks.iter().any(|c| c.noisy)
+  }
+
+  /// A one line summary of the controls and checks.
+  pub fn summary(&self) -> String {
+    let mut parts = vec![match self.pinned_cpu {
+      Some(cpu) => format!("pinned to CPU {cpu}"),
+      None => "not pinned".to_string(),
+    }];
+    parts.extend(self.nice.map(|nice| format!("nice {nice}")));
+    parts.extend(self.checks.iter().map(|c| format!("{} {}", c.name, c.value)));
+    parts.join(", ")
+  }
+
+  /// The noisy checks as a list for a warning.
+  pub fn warnings(&self) -> Vec<String> {
+    self.checks.iter().filter(|c| c.noisy).map(|c| format!("{}: {}", c.name, c.value)).collect()
+  }
+}
+
+/// Parse a Linux CPU list, such as "0-3,8".
+pub fn parse_cpu_list(text: &str) -> Vec<usize> {
+  let mut result = Vec::new();
+  for part in text.trim().split(',').filter(|p| !p.is_empty()) {
+    match part.split_once('-') {
+      Some((low, high)) => if let (Ok(low), Ok(high)) = (low.parse(), high.parse::<usize>()) {
+        result.extend(low..=high);
+      },
+      None => result.extend(part.parse::<usize>()),
+    }
+  }
+  result
+}
+
+/// Parse the busy and total time of each CPU from /proc/stat.
+pub fn parse_cpu_times(stat: &str) -> Vec<(usize, u64, u64)> {
+  let mut result = Vec::new();
+  for line in stat.lines() {
+    let mut words = line.split_whitespace();
+    let Some(cpu) = words.next().and_then(|w| w.strip_prefix("cpu"))
+        .and_then(|n| n.parse::<usize>().ok()) else {
+      continue;
+    };
+    let times: Vec<u64> = words.filter_map(|w| w.parse().ok()).collect();
+    // The fourth and fifth times are idle and waiting for I/O.
+    let idle = times.get(3).copied().unwrap_or(0) + times.get(4).copied().unwrap_or(0);
+    let total: u64 = times.iter().sum();
+    result.push((cpu, total - idle, total));
+  }
+  result
+}
+
+/// The fraction of the time that each CPU was busy between two samples
+/// of /proc/stat.
+pub fn busy_fractions(before: &str, after: &str) -> Vec<(usize, f64)> {
+  let before = parse_cpu_times(before);
+  parse_cpu_times(after).into_iter().filter_map(|(cpu, busy, total)| {
+    let (_, old_busy, old_total) = before.iter().find(|(c, _, _)| *c == cpu)?;
+    let elapsed = total.saturating_sub(*old_total);
+    (elapsed > 0).then(|| (cpu, busy.saturating_sub(*old_busy) as f64 / elapsed as f64))
+  }).collect()
+}
+
+#[cfg(target_os = "linux")]
+fn pin(cpu: usize) -> Result<(), String> {
+  if cpu >= libc::CPU_SETSIZE as usize {
+    return Err("no such CPU".to_string());
+  }
+  // SAFETY: the set is initialized before it is passed to the kernel,
+  // and pid 0 is the calling thread.
+  unsafe {
+    let mut set: libc::cpu_set_t = std::mem::zeroed();
+    libc::CPU_SET(cpu, &mut set);
+    if libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set) != 0 {
+      return Err(std::io::Error::last_os_error().to_string());
+    }
+  }
+  Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_nice(nice: i32) -> Result<(), String> {
+  // SAFETY: on Linux, who = 0 is the calling thread.
+  if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+    return Err(std::io::Error::last_os_error().to_string());
+  }
+  Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin(_cpu: usize) -> Result<(), String> {
+  Err("pinning needs Linux".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_nice(_nice: i32) -> Result<(), String> {
+  Err("setting the priority needs Linux".to_string())
+}
+
+/// Parse the value of the setting name.
+pub fn parse_setting<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+  value.trim().parse().map_err(|_| format!("bad {name} {value:?}"))
+}
+
+/// Whether the value of a flag turns it on, which is anything but empty
+/// or 0.
+pub fn parse_flag(value: &str) -> bool {
+  !matches!(value.trim(), "" | "0")
+}
+
+fn env_flag(name: &str) -> bool {
+  env::var(name).is_ok_and(|value| parse_flag(&value))
+}
+
+/// The setting from the environment, which is None when it is unset
+/// or empty.
+fn env_number<T: std::str::FromStr>(name: &str) -> Result<Option<T>, String> {
+  match env::var(name) {
+    Ok(value) if !value.trim().is_empty() => parse_setting(name, &value).map(Some),
+    _ => Ok(None),
+  }
+}
+
+fn check(name: &str, value: impl Into<String>, noisy: bool) -> Check {
+  Check { name: name.to_string(), value: value.into(), noisy }
+}
+
+/// Check the frequency scaling, turbo, and load of the machine. When
+/// the thread is pinned, also check its CPU and the CPU's SMT siblings.
+/// The load is skipped with watch_load false or without /proc/stat,
+/// so that it doesn't sleep for nothing.
+fn check_machine(pinned: Option<usize>, watch_load: bool, report: &mut NoiseReport) {
+  let cpu = pinned.unwrap_or(0);
+  let governor = read_trimmed(&format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq/scaling_governor"));
+  report.checks.push(match governor {
+    Some(governor) => check("governor", governor.clone(), governor != "performance"),
+    None => check("governor", "unknown", false),
+  });
+  report.checks.push(match read_turbo() {
+    Some(turbo) => check("turbo", if turbo { "on" } else { "off" }, turbo),
+    None => check("turbo", "unknown", false),
+  });
+  if !watch_load {
+    report.checks.push(check("load", "skipped", false));
+    return;
+  }
+  // Watch the CPUs for a moment to see if other processes are using them.
+  let Ok(before) = fs::read_to_string("/proc/stat") else {
+    report.checks.push(check("load", "unknown", false));
+    return;
+  };
+  thread::sleep(SAMPLE_TIME);
+  let Ok(after) = fs::read_to_string("/proc/stat") else {
+    report.checks.push(check("load", "unknown", false));
+    return;
+  };
+  let busy = busy_fractions(&before, &after);
+  let Some(cpu) = pinned else {
+    let busiest = busy.iter().map(|(_, b)| *b).fold(0.0, f64::max);
+    report.checks.push(check("busiest CPU load", format!("{:.0}%", busiest * 100.0),
+                             busiest > BUSY_LIMIT));
+    return;
+  };
+  // The benchmark thread is asleep, so any load on its CPU is from
+  // another process.
+  let own = busy.iter().find(|(c, _)| *c == cpu).map_or(0.0, |(_, b)| *b);
+  report.checks.push(check("pinned CPU load", format!("{:.0}%", own * 100.0), own > BUSY_LIMIT));
+  let siblings: Vec<usize> = read_trimmed(
+      &format!("/sys/devices/system/cpu/cpu{cpu}/topology/thread_siblings_list"))
+      .map(|list| parse_cpu_list(&list)).unwrap_or_default()
+      .into_iter().filter(|c| *c != cpu).collect();
+  if siblings.is_empty() {
+    report.checks.push(check("SMT sibling load", "no siblings", false));
+  } else {
+    let sibling = busy.iter().filter(|(c, _)| siblings.contains(c))
+        .map(|(_, b)| *b).fold(0.0, f64::max);
+    report.checks.push(check("SMT sibling load", format!("{:.0}%", sibling * 100.0),
+                             sibling > BUSY_LIMIT));
+  }
+}
+
+/// Apply the noise controls from the environment to the current thread
+/// and check the machine. A setting that doesn't parse and failing to
+/// pin or to raise the priority are recorded as noisy checks.
+pub fn prepare() -> NoiseReport {
+  let mut report = NoiseReport { strict: env_flag("RUST_BENCH_STRICT"),
+    ..NoiseReport::default() };
+  match env_number("RUST_BENCH_PIN") {
+    Ok(cpu) => report.pinned_cpu = cpu,
+    Err(err) => report.checks.push(check("pinning", err, true)),
+  }
+  match env_number("RUST_BENCH_NICE") {
+    Ok(nice) => report.nice = nice,
+    Err(err) => report.checks.push(check("priority", err, true)),
+  }
+  if let Some(cpu) = report.pinned_cpu {
+    if let Err(err) = pin(cpu) {
+      report.checks.push(check("pinning", format!("can't pin to CPU {cpu}: {err}"), true));
+      report.pinned_cpu = None;
+    }
+  }
+  if let Some(nice) = report.nice {
+    if let Err(err) = set_nice(nice) {
+      report.checks.push(check("priority", format!("can't set nice to {nice}: {err}"), true));
+      report.nice = None;
+    }
+  }
+  check_machine(report.pinned_cpu, !env_flag("RUST_BENCH_NO_LOAD"), &mut report);
+  report
+}
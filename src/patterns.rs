@@ -0,0 +1,105 @@
+// Copyright by Owen O'Malley 2024
+
+// Layouts of class ids for studying indirect branch prediction. Each
+// layout produces a sequence of class ids in 0..classes that the
+// dispatch benchmarks turn into objects, enums, or function indexes.
+
+use std::fmt;
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+/// Each class id is drawn uniformly at random.
+pub fn random(classes: usize, size: usize, seed: u64) -> Vec<usize> {
+  assert!(classes > 0, "there must be at least one class");
+  let mut rng = crate::seeded_rng(seed);
+  (0..size).map(|_| rng.gen_range(0..classes)).collect()
+}
+
+/// The classes are in equal sized blocks in ascending order.
+pub fn sorted_blocks(classes: usize, size: usize) -> Vec<usize> {
+  assert!(classes > 0, "there must be at least one class");
+  (0..size).map(|i| i * classes / size).collect()
+}
+
+/// The classes cycle 0, 1, ..., classes - 1 without any shuffling.
+pub fn tick_tock(classes: usize, size: usize) -> Vec<usize> {
+  assert!(classes > 0, "there must be at least one class");
+  (0..size).map(|i| i % classes).collect()
+}
+
+/// Build a window with copies of each class, shuffle it, and then
+/// repeat the window to fill the result. The window has a length of
+/// classes * copies.
+pub fn shuffled_window(classes: usize, copies: usize, size: usize, seed: u64) -> Vec<usize> {
+  assert!(classes > 0, "there must be at least one class");
+  assert!(copies > 0, "there must be at least one copy of each class");
+  let mut order = (0..classes * copies).map(|x| x / copies).collect::<Vec<usize>>();
+  let mut rng = crate::seeded_rng(seed);
+  order.shuffle(&mut rng);
+  (0..size).map(|i| order[i % order.len()]).collect()
+}
+
+/// Like shuffled_window, but with a window of any period, in which the
+/// classes occur as evenly as possible.
+pub fn shuffled_period(classes: usize, period: usize, size: usize, seed: u64) -> Vec<usize> {
+  assert!(classes > 0, "there must be at least one class");
+  assert!(period > 0, "the period must be positive");
+  let mut order = (0..period).map(|x| x % classes).collect::<Vec<usize>>();
+  let mut rng = crate::seeded_rng(seed);
+  order.shuffle(&mut rng);
+  (0..size).map(|i| order[i % order.len()]).collect()
+}
+
+/// A Markov chain that repeats the previous class with probability
+/// stay and otherwise moves to one of the other classes at random.
+pub fn markov(classes: usize, stay: f64, size: usize, seed: u64) -> Vec<usize> {
+  assert!(classes > 0, "there must be at least one class");
+  let mut rng = crate::seeded_rng(seed);
+  let mut current = rng.gen_range(0..classes);
+  (0..size).map(|_| {
+    if classes > 1 && !rng.gen_bool(stay) {
+      // Pick from the other classes by skipping over the current one.
+      let next = rng.gen_range(0..classes - 1);
+      current = if next >= current { next + 1 } else { next };
+    }
+    current
+  }).collect()
+}
+
+/// The named layouts, so that benchmarks can sweep over them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+  Random,
+  SortedBlocks,
+  TickTock,
+  ShuffledWindow { copies: usize },
+  ShuffledPeriod { period: usize },
+  Markov { stay: f64 },
+}
+
+impl Layout {
+  /// Generate size class ids in 0..classes with this layout.
+  pub fn generate(&self, classes: usize, size: usize, seed: u64) -> Vec<usize> {
+    match *self {
+      Layout::Random => random(classes, size, seed),
+      Layout::SortedBlocks => sorted_blocks(classes, size),
+      Layout::TickTock => tick_tock(classes, size),
+      Layout::ShuffledWindow { copies } => shuffled_window(classes, copies, size, seed),
+      Layout::ShuffledPeriod { period } => shuffled_period(classes, period, size, seed),
+      Layout::Markov { stay } => markov(classes, stay, size, seed),
+    }
+  }
+}
+
+impl fmt::Display for Layout {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Layout::Random => write!(f, "random"),
+      Layout::SortedBlocks => write!(f, "sorted"),
+      Layout::TickTock => write!(f, "ticktock"),
+      Layout::ShuffledWindow { copies } => write!(f, "rndrn {copies}"),
+      Layout::ShuffledPeriod { period } => write!(f, "period {period}"),
+      Layout::Markov { stay } => write!(f, "markov {stay}"),
+    }
+  }
+}
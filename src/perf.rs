@@ -0,0 +1,266 @@
+// Copyright by Owen O'Malley 2024
+
+// Count hardware events, such as branch misses and cache misses, with
+// Linux's perf_event_open. The timings only suggest why one form of
+// dispatch is faster than another, while the counters show whether the
+// branch predictor or the caches are the reason.
+//
+// Containers and virtual machines often don't expose the counters, so
+// opening them fails cleanly and the benchmarks only report times.
+
+use std::fmt;
+use std::io;
+use serde::{Deserialize, Serialize};
+
+/// The events per element of one benchmark. Each event is None if the
+/// machine couldn't count it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Counters {
+  pub instructions: Option<f64>,
+  pub cycles: Option<f64>,
+  pub branches: Option<f64>,
+  pub branch_misses: Option<f64>,
+  /// The loads and stores, which only the cache simulator counts.
+  #[serde(default)]
+  pub l1d_accesses: Option<f64>,
+  pub l1d_misses: Option<f64>,
+  pub llc_misses: Option<f64>,
+}
+
+impl Counters {
+  /// The fraction of the branches that were mispredicted.
+  pub fn branch_miss_rate(&self) -> Option<f64> {
+    Some(self.branch_misses? / self.branches?)
+  }
+
+  /// The instructions per cycle.
+  pub fn ipc(&self) -> Option<f64> {
+    Some(self.instructions? / self.cycles?)
+  }
+}
+
+impl fmt::Display for Counters {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut parts = Vec::new();
+    for (name, value) in [("instructions", self.instructions), ("cycles", self.cycles),
+        ("branch misses", self.branch_misses), ("L1D accesses", self.l1d_accesses),
+        ("L1D misses", self.l1d_misses),
+        ("LLC misses", self.llc_misses)] {
+      if let Some(value) = value {
+        parts.push(format!("{value:.3} {name}"));
+      }
+    }
+    write!(f, "{} per element", parts.join(", "))?;
+    if let Some(rate) = self.branch_miss_rate() {
+      write!(f, ", {:.2}% of branches missed", rate * 100.0)?;
+    }
+    Ok(())
+  }
+}
+
+/// The events that are counted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Event {
+  Instructions,
+  Cycles,
+  Branches,
+  BranchMisses,
+  L1dMisses,
+  LlcMisses,
+}
+
+const EVENTS: [Event; 6] = [Event::Instructions, Event::Cycles, Event::Branches,
+  Event::BranchMisses, Event::L1dMisses, Event::LlcMisses];
+
+impl Event {
+  fn set(self, counters: &mut Counters, value: f64) {
+    let field = match self {
+      Event::Instructions => &mut counters.instructions,
+      Event::Cycles => &mut counters.cycles,
+      Event::Branches => &mut counters.branches,
+      Event::BranchMisses => &mut counters.branch_misses,
+      Event::L1dMisses => &mut counters.l1d_misses,
+      Event::LlcMisses => &mut counters.llc_misses,
+    };
+    *field = Some(value);
+  }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+  use std::fs::File;
+  use std::io::{self, Read};
+  use std::os::fd::{AsRawFd, FromRawFd};
+  use super::Event;
+
+  const PERF_TYPE_HARDWARE: u32 = 0;
+  const PERF_TYPE_HW_CACHE: u32 = 3;
+  const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+  const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+  const PERF_COUNT_HW_BRANCH_INSTRUCTIONS: u64 = 4;
+  const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+  // The cache events are the cache | (operation << 8) | (result << 16).
+  const PERF_COUNT_HW_CACHE_L1D_READ_MISS: u64 = 1 << 16;
+  const PERF_COUNT_HW_CACHE_LL_READ_MISS: u64 = 2 | (1 << 16);
+  const PERF_FORMAT_TOTAL_TIME_ENABLED: u64 = 1;
+  const PERF_FORMAT_TOTAL_TIME_RUNNING: u64 = 2;
+  const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 8;
+  const FLAG_DISABLED: u64 = 1;
+  const FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+  const FLAG_EXCLUDE_HV: u64 = 1 << 6;
+  const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+  const PERF_EVENT_IOC_DISABLE: libc::c_ulong = 0x2401;
+  const PERF_EVENT_IOC_RESET: libc::c_ulong = 0x2403;
+
+  /// The first version of perf_event_attr, which every kernel accepts.
+  #[repr(C)]
+  #[derive(Default)]
+  struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+  }
+
+  /// An open counter for the current thread on any CPU.
+  pub struct Counter(File);
+
+  impl Counter {
+    pub fn open(event: Event) -> io::Result<Self> {
+      let (kind, config) = match event {
+        Event::Instructions => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_INSTRUCTIONS),
+        Event::Cycles => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_CPU_CYCLES),
+        Event::Branches => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_BRANCH_INSTRUCTIONS),
+        Event::BranchMisses => (PERF_TYPE_HARDWARE, PERF_COUNT_HW_BRANCH_MISSES),
+        Event::L1dMisses => (PERF_TYPE_HW_CACHE, PERF_COUNT_HW_CACHE_L1D_READ_MISS),
+        Event::LlcMisses => (PERF_TYPE_HW_CACHE, PERF_COUNT_HW_CACHE_LL_READ_MISS),
+      };
+      let attr = PerfEventAttr { kind,
+        size: size_of::<PerfEventAttr>() as u32,
+        config,
+        read_format: PERF_FORMAT_TOTAL_TIME_ENABLED | PERF_FORMAT_TOTAL_TIME_RUNNING,
+        flags: FLAG_DISABLED | FLAG_EXCLUDE_KERNEL | FLAG_EXCLUDE_HV,
+        ..Default::default() };
+      // SAFETY: attr is a valid perf_event_attr that outlives the call.
+      let fd = unsafe {
+        libc::syscall(libc::SYS_perf_event_open, &attr as *const PerfEventAttr, 0, -1, -1,
+                      PERF_FLAG_FD_CLOEXEC)
+      };
+      if fd < 0 {
+        return Err(io::Error::last_os_error());
+      }
+      // SAFETY: the kernel returned a new file descriptor that we own.
+      Ok(Counter(unsafe { File::from_raw_fd(fd as i32) }))
+    }
+
+    fn ioctl(&self, request: libc::c_ulong) -> io::Result<()> {
+      // SAFETY: the file descriptor is an open perf event.
+      if unsafe { libc::ioctl(self.0.as_raw_fd(), request, 0) } < 0 {
+        return Err(io::Error::last_os_error());
+      }
+      Ok(())
+    }
+
+    pub fn reset_and_enable(&self) -> io::Result<()> {
+      self.ioctl(PERF_EVENT_IOC_RESET)?;
+      self.ioctl(PERF_EVENT_IOC_ENABLE)
+    }
+
+    pub fn disable(&self) -> io::Result<()> {
+      self.ioctl(PERF_EVENT_IOC_DISABLE)
+    }
+
+    /// Read the count, scaled up for the time that the kernel
+    /// multiplexed the counter off of the hardware.
+    pub fn read(&mut self) -> io::Result<f64> {
+      let mut buffer = [0u8; 24];
+      self.0.read_exact(&mut buffer)?;
+      let word = |i: usize| u64::from_ne_bytes(buffer[i * 8..i * 8 + 8].try_into().unwrap());
+      let (value, enabled, running) = (word(0), word(1), word(2));
+      if running == 0 {
+        return Err(io::Error::other("the counter never ran"));
+      }
+      Ok(value as f64 * enabled as f64 / running as f64)
+    }
+  }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+  use std::io;
+  use super::Event;
+
+  pub struct Counter;
+
+  impl Counter {
+    pub fn open(_event: Event) -> io::Result<Self> {
+      Err(io::Error::new(io::ErrorKind::Unsupported, "perf counters need Linux"))
+    }
+
+    pub fn reset_and_enable(&self) -> io::Result<()> {
+      Ok(())
+    }
+
+    pub fn disable(&self) -> io::Result<()> {
+      Ok(())
+    }
+
+    pub fn read(&mut self) -> io::Result<f64> {
+      Ok(0.0)
+    }
+  }
+}
+
+/// The counters that could be opened on this machine.
+pub struct CounterSet {
+  counters: Vec<(Event, sys::Counter)>,
+}
+
+impl CounterSet {
+  /// Open every counter that the machine supports. It is an error if
+  /// none of them can be opened.
+  pub fn open() -> io::Result<Self> {
+    let mut counters = Vec::new();
+    let mut error = None;
+    for event in EVENTS {
+      match sys::Counter::open(event) {
+        Ok(counter) => counters.push((event, counter)),
+        Err(err) => error = Some(err),
+      }
+    }
+    match error {
+      Some(err) if counters.is_empty() => Err(err),
+      _ => Ok(CounterSet { counters }),
+    }
+  }
+
+  /// Count the events while calling f iterations times and divide them
+  /// by the total number of elements.
+  pub fn measure<R>(&mut self, iterations: u64, elements: u64,
+                    mut f: impl FnMut() -> R) -> io::Result<Counters> {
+    for (_, counter) in &self.counters {
+      counter.reset_and_enable()?;
+    }
+    for _ in 0..iterations {
+      std::hint::black_box(f());
+    }
+    for (_, counter) in &self.counters {
+      counter.disable()?;
+    }
+    let total = (iterations * elements.max(1)) as f64;
+    let mut result = Counters::default();
+    for (event, counter) in &mut self.counters {
+      // Leave out the events that the hardware didn't count.
+      if let Ok(value) = counter.read() {
+        event.set(&mut result, value / total);
+      }
+    }
+    Ok(result)
+  }
+}
@@ -0,0 +1,89 @@
+// Copyright by Owen O'Malley 2024
+
+// Characterize the indirect branch predictor. A shuffled sequence of
+// classes that repeats with a short period is nearly as fast as a single
+// class, because the predictor learns the whole period, while a long
+// period is as slow as random classes. Sweeping the number of classes
+// and the period on a grid finds the knee between them, and the period
+// at the knee estimates how many indirect branches the predictor's
+// history can hold.
+
+use serde::{Deserialize, Serialize};
+
+/// The default numbers of classes in the grid.
+pub const CLASSES: [usize; 6] = [2, 4, 8, 16, 32, 50];
+
+/// The default periods in the grid, in indirect calls.
+pub const PERIODS: [usize; 11] = [8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192];
+
+/// How much slower than the fastest period the random order has to be
+/// for a knee to be meaningful.
+const MIN_PENALTY: f64 = 0.2;
+
+/// The knee, which is between two periods of the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Knee {
+  /// The longest period that stays fast.
+  pub below: usize,
+  /// The shortest period that is slow from there on.
+  pub above: usize,
+}
+
+impl Knee {
+  /// The geometric middle of the two periods.
+  pub fn period(&self) -> f64 {
+    (self.below as f64 * self.above as f64).sqrt()
+  }
+}
+
+/// The sweep over the periods for one number of classes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClassSweep {
+  pub classes: usize,
+  /// The nanoseconds per call for each period.
+  pub periods: Vec<(usize, f64)>,
+  /// The nanoseconds per call with the classes in random order.
+  pub random_ns: f64,
+  /// None when the random order isn't much slower, or when no period
+  /// in the grid is slow.
+  pub knee: Option<Knee>,
+}
+
+/// The results of the experiment, which are saved with the run.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PredictorReport {
+  pub sweeps: Vec<ClassSweep>,
+  /// The median of the knees' periods, which estimates the number of
+  /// indirect branches in the predictor's history.
+  pub capacity: Option<f64>,
+}
+
+/// Find the knee of a sweep, which is where the time crosses halfway
+/// from the fastest period to the random order and stays above it.
+pub fn find_knee(periods: &[(usize, f64)], random_ns: f64) -> Option<Knee> {
+  let fastest = periods.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+  if periods.is_empty() || random_ns < fastest * (1.0 + MIN_PENALTY) {
+    return None;
+  }
+  let threshold = (fastest + random_ns) / 2.0;
+  let last_fast = periods.iter().rposition(|p| p.1 < threshold)?;
+  let above = periods.get(last_fast + 1)?;
+  Some(Knee { below: periods[last_fast].0, above: above.0 })
+}
+
+impl PredictorReport {
+  /// Build the report from the sweeps, finding each one's knee.
+  pub fn new(mut sweeps: Vec<ClassSweep>) -> Self {
+    for sweep in &mut sweeps {
+      sweep.knee = find_knee(&sweep.periods, sweep.random_ns);
+    }
+    let mut knees: Vec<f64> = sweeps.iter().filter_map(|s| s.knee.map(|k| k.period())).collect();
+    knees.sort_by(f64::total_cmp);
+    let capacity = match knees.len() {
+      0 => None,
+      n if n % 2 == 1 => Some(knees[n / 2]),
+      n => Some((knees[n / 2 - 1] + knees[n / 2]) / 2.0),
+    };
+    PredictorReport { sweeps, capacity }
+  }
+}
@@ -0,0 +1,164 @@
+// Copyright by Owen O'Malley 2024
+
+// Generate the result lists in the README from a saved run. Each list
+// is between a pair of marker comments:
+//
+//   <!-- results tail -->
+//   ...
+//   <!-- end results -->
+//
+// and the sections below define which benchmarks go in each list and
+// how they are grouped.
+
+use std::collections::HashMap;
+use crate::results::{BenchResult, Measurement, Run};
+
+/// A named list of benchmarks in the README. Each group has a heading,
+/// such as "fast", and the ids of its benchmarks. A group with an empty
+/// heading is written as a flat list.
+pub struct Section {
+  pub name: &'static str,
+  pub groups: &'static [(&'static str, &'static [&'static str])],
+}
+
+pub const SECTIONS: &[Section] = &[
+  Section { name: "dispatch", groups: &[
+    ("single", &["dispatch lambda", "dispatch func", "dispatch func template"]),
+    ("multiple", &["dispatch lambdas", "dispatch sort lambdas", "dispatch template objs",
+      "dispatch template box objs", "dispatch random/objs/10", "dispatch enum/10"]),
+  ]},
+  Section { name: "branching", groups: &[
+    ("lookup", &["lookup array", "lookup hashmap"]),
+    ("branching", &["branching iter match", "branching match result", "branching for match",
+      "branching iter if", "branching for if"]),
+  ]},
+  Section { name: "tail", groups: &[
+    ("painful", &["tail match"]),
+    ("slow", &["tail match accum", "tail len match accum"]),
+    ("fast", &["tail match2 accum", "tail if idx accum", "tail if accum", "tail if"]),
+  ]},
+  Section { name: "option", groups: &[
+    ("slow", &["option for if", "option for if_let", "option for match"]),
+    ("fast", &["option filer_map", "option match sum", "option map_or"]),
+  ]},
+  Section { name: "element", groups: &[
+    ("", &["element index array", "element for array", "element sum array",
+      "element fold array", "element index vec", "element for vec", "element sum vec",
+      "element fold vec"]),
+  ]},
+  Section { name: "sliding", groups: &[
+    ("", &["sliding window", "sliding for"]),
+  ]},
+  Section { name: "dual", groups: &[
+    ("", &["dual zip", "dual for"]),
+  ]},
+];
+
+const BEGIN_MARKER: &str = "<!-- results ";
+const END_MARKER: &str = "<!-- end results -->";
+
+/// Format a number with 4 significant digits like Criterion does.
+fn short(n: f64) -> String {
+  if n < 10.0 {
+    format!("{n:.4}")
+  } else if n < 100.0 {
+    format!("{n:.3}")
+  } else if n < 1000.0 {
+    format!("{n:.2}")
+  } else {
+    format!("{n:.1}")
+  }
+}
+
+/// Format a time in nanoseconds with the units that Criterion uses.
+pub fn format_time(ns: f64) -> String {
+  if ns < 1.0 {
+    format!("{} ps", short(ns * 1e3))
+  } else if ns < 1e3 {
+    format!("{} ns", short(ns))
+  } else if ns < 1e6 {
+    format!("{} µs", short(ns / 1e3))
+  } else if ns < 1e9 {
+    format!("{} ms", short(ns / 1e6))
+  } else {
+    format!("{} s", short(ns / 1e9))
+  }
+}
+
+/// Format an estimate of a run with the given measurement.
+pub fn format_value(measurement: Measurement, value: f64) -> String {
+  match measurement {
+    Measurement::WallTime => format_time(value),
+    Measurement::Instructions => format!("{value:.0} instrs"),
+  }
+}
+
+/// Format a benchmark the way that Criterion prints it.
+pub fn format_result(result: &BenchResult) -> String {
+  format!("{:<23} time:   [{} {} {}]", result.id, format_time(result.lower),
+          format_time(result.estimate), format_time(result.upper))
+}
+
+/// Render the lines of a section. Benchmarks that aren't in the run
+/// are left out, and None means that none of them were in the run.
+pub fn render(section: &Section, results: &HashMap<&str, &BenchResult>) -> Option<Vec<String>> {
+  let mut lines = Vec::new();
+  let mut found = false;
+  for (heading, ids) in section.groups {
+    let indent = if heading.is_empty() { "" } else { "  " };
+    if !heading.is_empty() {
+      lines.push(format!("* {heading}"));
+    }
+    for id in *ids {
+      if let Some(result) = results.get(id) {
+        lines.push(format!("{indent}* {}", format_result(result)));
+        found = true;
+      }
+    }
+  }
+  found.then_some(lines)
+}
+
+/// Rewrite the lists between the markers in the README text with the
+/// results of the run. Returns the new text and the names of the
+/// sections that were updated.
+pub fn rewrite(readme: &str, run: &Run) -> Result<(String, Vec<String>), String> {
+  if run.measurement != Measurement::WallTime {
+    return Err(format!("The README lists times, but run {} counted {}", run.run_id,
+                       run.measurement));
+  }
+  let results: HashMap<&str, &BenchResult> = run.benchmarks.iter()
+      .map(|r| (r.id.as_str(), r)).collect();
+  let mut output = Vec::new();
+  let mut updated = Vec::new();
+  let mut lines = readme.lines();
+  while let Some(line) = lines.next() {
+    output.push(line.to_string());
+    let Some(name) = line.strip_prefix(BEGIN_MARKER).and_then(|l| l.strip_suffix(" -->")) else {
+      continue;
+    };
+    let section = SECTIONS.iter().find(|s| s.name == name)
+        .ok_or_else(|| format!("Unknown section {name}"))?;
+    let mut old = Vec::new();
+    loop {
+      match lines.next() {
+        Some(END_MARKER) => break,
+        Some(l) => old.push(l.to_string()),
+        None => return Err(format!("Section {name} is missing {END_MARKER}")),
+      }
+    }
+    match render(section, &results) {
+      Some(new) => {
+        output.extend(new);
+        updated.push(name.to_string());
+      }
+      None => output.extend(old),
+    }
+    output.push(END_MARKER.to_string());
+  }
+  let mut text = output.join("\n");
+  if readme.ends_with('\n') {
+    text.push('\n');
+  }
+  Ok((text, updated))
+}
@@ -0,0 +1,392 @@
+// Copyright by Owen O'Malley 2024
+
+// Export the results of a run as one JSON document and one CSV with a
+// stable schema, so that other tools don't need to read Criterion's
+// internal files. Each run is saved in its own directory under
+// target/rust-bench/runs.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::machine::Fingerprint;
+use crate::noise::NoiseReport;
+use crate::perf::Counters;
+use crate::predictor::PredictorReport;
+
+/// The version of the schema, which changes when fields are removed or
+/// change meaning.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The result of one benchmark. The estimates are per iteration of the
+/// benchmark in the unit of the run's measurement, which is nanoseconds
+/// for timed runs and instructions when the run counted instructions.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BenchResult {
+  /// The full name, such as "dispatch ticktock/objs/2".
+  pub id: String,
+  pub group: String,
+  pub function: Option<String>,
+  pub parameter: Option<String>,
+  /// The best estimate, which is the slope of the regression when
+  /// Criterion computed one and the mean otherwise.
+  pub estimate: f64,
+  pub lower: f64,
+  pub upper: f64,
+  pub confidence_level: f64,
+  pub mean: f64,
+  pub median: f64,
+  pub std_dev: f64,
+  /// The number of elements that each iteration processes.
+  pub elements: Option<u64>,
+  pub elements_per_second: Option<f64>,
+  /// The number of bytes that each iteration processes.
+  pub bytes: Option<u64>,
+  /// The average per iteration of each of the samples.
+  pub samples: Vec<f64>,
+  /// The hardware events per element, when the run counted them.
+  #[serde(default)]
+  pub counters: Option<Counters>,
+}
+
+impl BenchResult {
+  /// Build the result of a benchmark that was measured outside of
+  /// Criterion from the value per iteration of each sample. The estimate
+  /// is the median and the bounds are the smallest and largest samples.
+  pub fn from_samples(id: &str, group: &str, function: Option<&str>, parameter: Option<&str>,
+                      elements: u64, samples: Vec<f64>, counters: Option<Counters>) -> Self {
+    let mut sorted = samples.clone();
+    sorted.sort_by(f64::total_cmp);
+    let n = samples.len().max(1) as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    let median = sorted.get(sorted.len() / 2).copied().unwrap_or(0.0);
+    let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+    BenchResult { id: id.to_string(), group: group.to_string(),
+      function: function.map(str::to_string), parameter: parameter.map(str::to_string),
+      estimate: median, lower: sorted.first().copied().unwrap_or(0.0),
+      upper: sorted.last().copied().unwrap_or(0.0), confidence_level: 1.0, mean, median,
+      std_dev: variance.sqrt(), elements: (elements > 0).then_some(elements),
+      elements_per_second: None, bytes: None, samples, counters }
+  }
+}
+
+/// What the estimates of a run measure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Measurement {
+  /// Criterion's timings in nanoseconds.
+  #[default]
+  WallTime,
+  /// The retired instructions, which don't depend on the timer or the
+  /// rest of the machine.
+  Instructions,
+}
+
+impl Measurement {
+  /// The unit of the estimates, which is per iteration.
+  pub fn unit(&self) -> &'static str {
+    match self {
+      Measurement::WallTime => "ns",
+      Measurement::Instructions => "instructions",
+    }
+  }
+}
+
+impl fmt::Display for Measurement {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Measurement::WallTime => write!(f, "wall time"),
+      Measurement::Instructions => write!(f, "instructions"),
+    }
+  }
+}
+
+/// All of the results from one run along with the machine that ran them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+  pub schema_version: u32,
+  pub run_id: String,
+  /// The name of the compiler configuration from $RUST_BENCH_CONFIG, which
+  /// the matrix runner sets.
+  #[serde(default)]
+  pub config: Option<String>,
+  /// The seed of the benchmarks' random data from $RUST_BENCH_SEED.
+  #[serde(default)]
+  pub seed: u64,
+  #[serde(default)]
+  pub measurement: Measurement,
+  /// The start of the run in seconds since the Unix epoch.
+  pub started: u64,
+  pub machine: Fingerprint,
+  /// The noise controls and the checks of the machine before timing.
+  #[serde(default)]
+  pub noise: Option<NoiseReport>,
+  /// The estimate of the indirect branch predictor from the predictor
+  /// experiment.
+  #[serde(default)]
+  pub predictor: Option<PredictorReport>,
+  pub benchmarks: Vec<BenchResult>,
+}
+
+/// An empty run with the current schema, which is a base for building
+/// runs in tests and tools.
+impl Default for Run {
+  fn default() -> Self {
+    Run { schema_version: SCHEMA_VERSION, run_id: String::new(), config: None, seed: 0,
+      measurement: Measurement::WallTime, started: 0, machine: Fingerprint::default(),
+      noise: None, predictor: None, benchmarks: Vec::new() }
+  }
+}
+
+// The parts of Criterion's files that the exporter uses.
+
+#[derive(Deserialize)]
+struct CriterionBenchmark {
+  group_id: String,
+  function_id: Option<String>,
+  value_str: Option<String>,
+  throughput: Option<CriterionThroughput>,
+  full_id: String,
+}
+
+#[derive(Deserialize)]
+enum CriterionThroughput {
+  Bytes(u64),
+  BytesDecimal(u64),
+  Elements(u64),
+}
+
+#[derive(Deserialize)]
+struct CriterionInterval {
+  confidence_level: f64,
+  lower_bound: f64,
+  upper_bound: f64,
+}
+
+#[derive(Deserialize)]
+struct CriterionEstimate {
+  confidence_interval: CriterionInterval,
+  point_estimate: f64,
+}
+
+#[derive(Deserialize)]
+struct CriterionEstimates {
+  mean: CriterionEstimate,
+  median: CriterionEstimate,
+  std_dev: CriterionEstimate,
+  slope: Option<CriterionEstimate>,
+}
+
+#[derive(Deserialize)]
+struct CriterionSample {
+  iters: Vec<f64>,
+  times: Vec<f64>,
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> io::Result<T> {
+  Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Read the result from one of Criterion's "new" directories.
+fn read_result(dir: &Path) -> io::Result<BenchResult> {
+  let benchmark: CriterionBenchmark = read_json(&dir.join("benchmark.json"))?;
+  let estimates: CriterionEstimates = read_json(&dir.join("estimates.json"))?;
+  let sample: CriterionSample = read_json(&dir.join("sample.json"))?;
+  let best = estimates.slope.as_ref().unwrap_or(&estimates.mean);
+  let (elements, bytes) = match benchmark.throughput {
+    Some(CriterionThroughput::Elements(n)) => (Some(n), None),
+    Some(CriterionThroughput::Bytes(n) | CriterionThroughput::BytesDecimal(n)) => (None, Some(n)),
+    None => (None, None),
+  };
+  Ok(BenchResult {
+    id: benchmark.full_id,
+    group: benchmark.group_id,
+    function: benchmark.function_id,
+    parameter: benchmark.value_str,
+    estimate: best.point_estimate,
+    lower: best.confidence_interval.lower_bound,
+    upper: best.confidence_interval.upper_bound,
+    confidence_level: best.confidence_interval.confidence_level,
+    mean: estimates.mean.point_estimate,
+    median: estimates.median.point_estimate,
+    std_dev: estimates.std_dev.point_estimate,
+    elements,
+    elements_per_second: elements.map(|n| n as f64 * 1e9 / best.point_estimate),
+    bytes,
+    samples: sample.times.iter().zip(sample.iters.iter()).map(|(t, i)| t / i).collect(),
+    counters: None,
+  })
+}
+
+/// Recursively find Criterion's "new" directories.
+fn find_new_dirs(dir: &Path, result: &mut Vec<PathBuf>) -> io::Result<()> {
+  for entry in fs::read_dir(dir)? {
+    let path = entry?.path();
+    if path.is_dir() {
+      if path.file_name().is_some_and(|name| name == "new") {
+        if path.join("benchmark.json").exists() {
+          result.push(path);
+        }
+      } else {
+        find_new_dirs(&path, result)?;
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Collect the results of every benchmark in Criterion's directory
+/// that was measured at or after since.
+pub fn collect(criterion_home: &Path, since: Option<SystemTime>) -> io::Result<Vec<BenchResult>> {
+  let mut dirs = Vec::new();
+  if criterion_home.exists() {
+    find_new_dirs(criterion_home, &mut dirs)?;
+  }
+  let mut result = Vec::new();
+  for dir in dirs {
+    let modified = fs::metadata(dir.join("estimates.json"))?.modified()?;
+    if since.is_none_or(|since| modified >= since) {
+      result.push(read_result(&dir)?);
+    }
+  }
+  result.sort_by(|a, b| a.id.cmp(&b.id));
+  Ok(result)
+}
+
+/// The directory that holds the saved runs, which is
+/// $CARGO_TARGET_DIR/rust-bench/runs or target/rust-bench/runs.
+pub fn runs_dir() -> PathBuf {
+  env::var_os("CARGO_TARGET_DIR").map_or_else(|| PathBuf::from("target"), PathBuf::from)
+      .join("rust-bench").join("runs")
+}
+
+/// Find the most recent run in the runs directory.
+pub fn latest_run() -> io::Result<PathBuf> {
+  latest_run_in(&runs_dir())
+}
+
+/// Find the most recent run in the given runs directory.
+pub fn latest_run_in(dir: &Path) -> io::Result<PathBuf> {
+  let mut runs: Vec<PathBuf> = fs::read_dir(dir)?
+      .flatten()
+      .map(|e| e.path())
+      .filter(|p| p.join("results.json").exists())
+      .collect();
+  // The run ids are timestamps, so they sort in time order.
+  runs.sort();
+  runs.pop().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no saved runs"))
+}
+
+/// Format a time as a run id in UTC, such as 20240501-134502.
+pub fn run_id(time: SystemTime) -> String {
+  let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+  let (days, rem) = (secs / 86_400, secs % 86_400);
+  // Convert the days to a civil date with Howard Hinnant's algorithm.
+  let z = days as i64 + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z - era * 146_097;
+  let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + i64::from(month <= 2);
+  format!("{year:04}{month:02}{day:02}-{:02}{:02}{:02}", rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// Quote a CSV field if it needs it.
+fn csv_field(text: &str) -> String {
+  if text.contains([',', '"', '\n']) {
+    format!("\"{}\"", text.replace('"', "\"\""))
+  } else {
+    text.to_string()
+  }
+}
+
+fn csv_option<T: ToString>(value: &Option<T>) -> String {
+  value.as_ref().map_or_else(String::new, |v| csv_field(&v.to_string()))
+}
+
+/// The name of the build configuration from $RUST_BENCH_CONFIG, which
+/// the matrix sets for each configuration.
+pub fn config_from_env() -> Option<String> {
+  env::var("RUST_BENCH_CONFIG").ok().filter(|c| !c.is_empty())
+}
+
+impl Run {
+  /// Create a run from the results that the machine measured since it
+  /// started with the build configuration and the data seed.
+  pub fn new(started: SystemTime, config: Option<String>, seed: u64, machine: Fingerprint,
+             measurement: Measurement, benchmarks: Vec<BenchResult>) -> Self {
+    let mut id = run_id(started);
+    if let Some(config) = &config {
+      id = format!("{id}-{config}");
+    }
+    if seed != 0 {
+      id = format!("{id}-seed{seed}");
+    }
+    if measurement == Measurement::Instructions {
+      id = format!("{id}-instructions");
+    }
+    Run { schema_version: SCHEMA_VERSION,
+      run_id: id,
+      config,
+      seed,
+      measurement,
+      started: started.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+      machine,
+      noise: None,
+      predictor: None,
+      benchmarks }
+  }
+
+  /// Format the benchmarks as CSV. Each row repeats the run and machine,
+  /// so that the files from several runs can be concatenated.
+  pub fn to_csv(&self) -> String {
+    let mut result = String::from("run_id,config,seed,measurement,cpu_model,rustc,id,group,\
+      function,parameter,unit,estimate,lower,upper,mean,median,std_dev,elements,\
+      elements_per_second,bytes,instructions,cycles,branches,branch_misses,l1d_accesses,\
+      l1d_misses,llc_misses\n");
+    for bench in &self.benchmarks {
+      let counters = bench.counters.unwrap_or_default();
+      let fields = [csv_field(&self.run_id), csv_option(&self.config),
+        self.seed.to_string(), self.measurement.to_string(),
+        csv_field(&self.machine.cpu_model), csv_field(&self.machine.rustc),
+        csv_field(&bench.id), csv_field(&bench.group),
+        csv_option(&bench.function), csv_option(&bench.parameter),
+        self.measurement.unit().to_string(), bench.estimate.to_string(),
+        bench.lower.to_string(), bench.upper.to_string(),
+        bench.mean.to_string(), bench.median.to_string(), bench.std_dev.to_string(),
+        csv_option(&bench.elements), csv_option(&bench.elements_per_second),
+        csv_option(&bench.bytes), csv_option(&counters.instructions),
+        csv_option(&counters.cycles), csv_option(&counters.branches),
+        csv_option(&counters.branch_misses), csv_option(&counters.l1d_accesses),
+        csv_option(&counters.l1d_misses), csv_option(&counters.llc_misses)];
+      result.push_str(&fields.join(","));
+      result.push('\n');
+    }
+    result
+  }
+
+  /// Write results.json and results.csv into the run's directory under
+  /// runs_dir and return the directory.
+  pub fn save(&self) -> io::Result<PathBuf> {
+    let dir = runs_dir().join(&self.run_id);
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join("results.json"), serde_json::to_string_pretty(self)?)?;
+    fs::write(dir.join("results.csv"), self.to_csv())?;
+    Ok(dir)
+  }
+
+  /// Read a run from either its directory or its results.json.
+  pub fn read(path: &Path) -> io::Result<Self> {
+    if path.is_dir() {
+      read_json(&path.join("results.json"))
+    } else {
+      read_json(path)
+    }
+  }
+}
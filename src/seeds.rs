@@ -0,0 +1,65 @@
+// Copyright by Owen O'Malley 2024
+
+// Measure how much the results depend on the random input. The seed
+// sweep runs the benchmarks once per seed and, for each benchmark,
+// separates the spread of the estimates across the seeds from the
+// timer's noise within each run, which is the width of Criterion's
+// confidence interval. The values are in the unit of the runs'
+// measurement.
+
+use std::collections::BTreeMap;
+use crate::results::{Measurement, Run};
+
+/// The spread of one benchmark across the runs with different seeds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeedSpread {
+  pub id: String,
+  /// The seeds of the runs that have the benchmark.
+  pub seeds: Vec<u64>,
+  /// What the estimates measure, which is from the first run.
+  pub measurement: Measurement,
+  /// The mean of the estimates across the seeds.
+  pub mean: f64,
+  /// The sample standard deviation of the estimates across the seeds.
+  pub seed_std_dev: f64,
+  pub min: f64,
+  pub max: f64,
+  /// The mean half-width of the confidence intervals within the runs.
+  pub noise: f64,
+}
+
+impl SeedSpread {
+  /// Whether the input moves the result more than the timer's noise.
+  pub fn depends_on_data(&self) -> bool {
+    self.seed_std_dev > self.noise
+  }
+}
+
+/// The seed, estimate, and noise of a benchmark in one run.
+type SeedValue = (u64, f64, f64);
+
+/// Compute the spread of each benchmark across the runs, in the order of
+/// the benchmarks' ids.
+pub fn spread(runs: &[Run]) -> Vec<SeedSpread> {
+  let mut by_id: BTreeMap<&str, (Measurement, Vec<SeedValue>)> = BTreeMap::new();
+  for run in runs {
+    for bench in &run.benchmarks {
+      by_id.entry(&bench.id).or_insert_with(|| (run.measurement, Vec::new())).1
+          .push((run.seed, bench.estimate, (bench.upper - bench.lower) / 2.0));
+    }
+  }
+  by_id.into_iter().map(|(id, (measurement, values))| {
+    let n = values.len() as f64;
+    let mean = values.iter().map(|v| v.1).sum::<f64>() / n;
+    let seed_std_dev = if values.len() > 1 {
+      (values.iter().map(|v| (v.1 - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+    } else {
+      0.0
+    };
+    SeedSpread { id: id.to_string(), seeds: values.iter().map(|v| v.0).collect(), measurement,
+      mean, seed_std_dev,
+      min: values.iter().map(|v| v.1).fold(f64::INFINITY, f64::min),
+      max: values.iter().map(|v| v.1).fold(f64::NEG_INFINITY, f64::max),
+      noise: values.iter().map(|v| v.2).sum::<f64>() / n }
+  }).collect()
+}
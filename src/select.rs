@@ -0,0 +1,134 @@
+// Copyright by Owen O'Malley 2024
+
+// Select the benchmarks by their structure instead of by substrings of
+// their ids, which overlap between the categories. Each benchmark has a
+// category, which is its module in benches, a group, which is its
+// Criterion group or the name of a lone benchmark, an optional variant,
+// which is the function within the group, and an optional parameter.
+// The selection comes from the environment, since Criterion rejects the
+// arguments that it doesn't know:
+//
+//   RUST_BENCH_CATEGORY=a,b   the categories, such as dispatch
+//   RUST_BENCH_GROUP=a,b      the groups, such as dispatch random
+//   RUST_BENCH_VARIANT=a,b    the variants, such as objs
+//   RUST_BENCH_PARAMS=RANGE   the numeric parameters, such as 8..=12
+//   RUST_BENCH_LIST=1         list the selected benchmarks instead
+//
+// The names must match exactly and an empty list selects everything.
+
+use std::env;
+use std::fmt;
+use std::ops::Bound;
+use std::str::FromStr;
+
+/// The structure of one benchmark.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bench<'a> {
+  pub category: &'a str,
+  pub group: &'a str,
+  pub variant: Option<&'a str>,
+  pub parameter: Option<&'a str>,
+}
+
+impl fmt::Display for Bench<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.group)?;
+    if let Some(variant) = self.variant {
+      write!(f, "/{variant}")?;
+    }
+    if let Some(parameter) = self.parameter {
+      write!(f, "/{parameter}")?;
+    }
+    Ok(())
+  }
+}
+
+/// A range of numeric parameters, such as 8..=12, 8..12, 8.., ..=12,
+/// or 8.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamRange {
+  pub low: Bound<f64>,
+  pub high: Bound<f64>,
+}
+
+impl ParamRange {
+  pub fn contains(&self, value: f64) -> bool {
+    let above = match self.low {
+      Bound::Included(low) => value >= low,
+      Bound::Excluded(low) => value > low,
+      Bound::Unbounded => true,
+    };
+    let below = match self.high {
+      Bound::Included(high) => value <= high,
+      Bound::Excluded(high) => value < high,
+      Bound::Unbounded => true,
+    };
+    above && below
+  }
+}
+
+impl FromStr for ParamRange {
+  type Err = String;
+
+  fn from_str(text: &str) -> Result<Self, String> {
+    let number = |s: &str| s.trim().parse::<f64>()
+        .map_err(|_| format!("Bad number {s:?} in the parameter range {text:?}"));
+    let Some((low, high)) = text.split_once("..") else {
+      let value = number(text)?;
+      return Ok(ParamRange { low: Bound::Included(value), high: Bound::Included(value) });
+    };
+    let low = if low.trim().is_empty() { Bound::Unbounded } else { Bound::Included(number(low)?) };
+    let high = match high.strip_prefix('=') {
+      Some(high) => Bound::Included(number(high)?),
+      None if high.trim().is_empty() => Bound::Unbounded,
+      None => Bound::Excluded(number(high)?),
+    };
+    Ok(ParamRange { low, high })
+  }
+}
+
+/// Which benchmarks to run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+  pub categories: Vec<String>,
+  pub groups: Vec<String>,
+  pub variants: Vec<String>,
+  /// Only the benchmarks with a numeric parameter in the range.
+  pub parameters: Option<ParamRange>,
+  /// List the selected benchmarks instead of running them.
+  pub list: bool,
+}
+
+fn env_list(name: &str) -> Vec<String> {
+  env::var(name).unwrap_or_default().split(',').map(str::trim)
+      .filter(|s| !s.is_empty()).map(str::to_string).collect()
+}
+
+fn wanted(list: &[String], name: Option<&str>) -> bool {
+  list.is_empty() || name.is_some_and(|name| list.iter().any(|n| n == name))
+}
+
+impl Selection {
+  /// Read the selection from the environment.
+  pub fn from_env() -> Result<Self, String> {
+    let parameters = match env::var("RUST_BENCH_PARAMS") {
+      Ok(range) if !range.trim().is_empty() => Some(range.parse()?),
+      _ => None,
+    };
+    Ok(Selection { categories: env_list("RUST_BENCH_CATEGORY"), groups: env_list("RUST_BENCH_GROUP"),
+      variants: env_list("RUST_BENCH_VARIANT"), parameters,
+      list: env::var_os("RUST_BENCH_LIST").is_some() })
+  }
+
+  /// Whether any of the benchmarks in the category may be selected.
+  pub fn wants_category(&self, category: &str) -> bool {
+    wanted(&self.categories, Some(category))
+  }
+
+  pub fn wants(&self, bench: &Bench) -> bool {
+    self.wants_category(bench.category) && wanted(&self.groups, Some(bench.group))
+        && wanted(&self.variants, bench.variant)
+        && self.parameters.is_none_or(|range| bench.parameter
+            .and_then(|p| p.parse::<f64>().ok()).is_some_and(|p| range.contains(p)))
+  }
+}
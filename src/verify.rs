@@ -0,0 +1,20 @@
+// Copyright by Owen O'Malley 2024
+
+// Checks that the variants of a benchmark group compute the same
+// answer, so that a broken variant can't post a misleadingly fast time.
+
+use std::fmt::Debug;
+
+/// Panic unless every variant's result equals the expected result.
+pub fn check_variants<T: PartialEq + Debug>(group: &str, expected: T, results: &[(&str, T)]) {
+  for (variant, result) in results {
+    assert_eq!(*result, expected, "{group} {variant} disagrees with the expected result");
+  }
+}
+
+/// Convert a reference result that was computed in i64 to the i32 that
+/// the benchmarks compute. Panics if the benchmarks' i32 arithmetic
+/// would overflow, since the variants would then silently wrap.
+pub fn expect_i32(group: &str, value: i64) -> i32 {
+  i32::try_from(value).unwrap_or_else(|_| panic!("{group} overflows i32 with {value}"))
+}
@@ -0,0 +1,69 @@
+// Copyright by Owen O'Malley 2024
+
+use rust_bench::asm::{analyze, kernel_names, kernels, parse_objdump, parse_relocations,
+  resolve_slots};
+
+const OBJDUMP: &str = "
+bench:     file format elf64-x86-64
+
+Disassembly of section .text:
+
+00000000002071f0 <rust_bench::kernels::tail::sum_via_match>:
+  2071f0:\tcmp    rsi,0x1
+  2071f4:\tje     2071fe <rust_bench::kernels::tail::sum_via_match+0xe>
+  2071fe:\tmov    eax,DWORD PTR [rdi]
+  20720f:\tcall   QWORD PTR [rip+0x1f05c3]        # 3f77d8 <_DYNAMIC+0x438>
+  207218:\tret
+
+0000000000207220 <rust_bench::kernels::elements::sum_via_sum>:
+  207220:\tpxor   xmm0,xmm0
+  207224:\tpaddd  xmm0,XMMWORD PTR [rdi+rax*4]
+  20722a:\tadd    rax,0x4
+  20722e:\tcmp    rax,rsi
+  207231:\tjne    207224 <rust_bench::kernels::elements::sum_via_sum+0x4>
+  207233:\tnotrack jmp rcx
+  207236:\tjmp    2051f0 <<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop+0x10>
+";
+
+const RELOCATIONS: &str = "
+DYNAMIC RELOCATION RECORDS
+OFFSET           TYPE              VALUE
+00000000003f77d8 R_X86_64_RELATIVE  *ABS*+0x00000000002071f0
+00000000003f7800 R_X86_64_GLOB_DAT  __cxa_finalize@GLIBC_2.2.5
+";
+
+#[test]
+fn kernel_names_come_from_the_sources() {
+  let names = kernel_names();
+  assert!(names.contains(&"tail::sum_via_match".to_string()));
+  assert!(names.contains(&"dispatch::iter_objs".to_string()));
+  assert!(names.contains(&"interpreter::run_match".to_string()));
+  assert!(!names.iter().any(|n| n.ends_with("::check") || n.ends_with("::verify")));
+}
+
+#[test]
+fn analyze_listings() {
+  let mut functions = parse_objdump(OBJDUMP);
+  assert_eq!(functions.len(), 2);
+  assert_eq!(functions[1].instructions[5].mnemonic, "jmp");
+  let slots = parse_relocations(RELOCATIONS, &functions);
+  assert_eq!(slots.len(), 2);
+  resolve_slots(&mut functions, &slots);
+
+  let tail = analyze(&functions[0]);
+  assert_eq!(tail.instructions, 5);
+  assert_eq!(tail.loops, 0);
+  assert!(!tail.vectorized);
+  assert!(tail.recursive);
+  assert_eq!(tail.calls, vec!["rust_bench::kernels::tail::sum_via_match"]);
+
+  let sum = analyze(&functions[1]);
+  assert_eq!(sum.loops, 1);
+  assert!(sum.vectorized);
+  assert!(!sum.recursive);
+  assert_eq!(sum.calls, vec!["<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop"]);
+
+  let found = kernels(&functions);
+  assert_eq!(found["tail::sum_via_match"].len(), 1);
+  assert!(found["tail::sum_via_if"].is_empty());
+}
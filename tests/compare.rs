@@ -0,0 +1,64 @@
+// Copyright by Owen O'Malley 2024
+
+use rust_bench::compare::{compare, mann_whitney, Verdict};
+use rust_bench::results::{BenchResult, Run};
+
+fn result(id: &str, samples: Vec<f64>) -> BenchResult {
+  let estimate = samples.iter().sum::<f64>() / samples.len() as f64;
+  BenchResult { id: id.to_string(), group: id.to_string(), estimate, samples,
+    ..Default::default() }
+}
+
+fn run(benchmarks: Vec<BenchResult>) -> Run {
+  Run { benchmarks, ..Default::default() }
+}
+
+/// Samples around the given time with a little deterministic noise.
+fn samples(ns: f64) -> Vec<f64> {
+  (0..20).map(|i| ns * (1.0 + (i % 5) as f64 * 0.002)).collect()
+}
+
+#[test]
+fn mann_whitney_p_values() {
+  let a: Vec<f64> = (0..10).map(f64::from).collect();
+  let b: Vec<f64> = (10..20).map(f64::from).collect();
+  // U = 0 for n1 = n2 = 10 gives z = 3.74 with the continuity correction.
+  assert!((mann_whitney(&a, &b) - 1.8e-4).abs() < 1e-5);
+  assert_eq!(mann_whitney(&a, &a), 1.0);
+  let interleaved: Vec<f64> = (0..10).map(|x| x as f64 + 0.5).collect();
+  assert!(mann_whitney(&a, &interleaved) > 0.5);
+  assert_eq!(mann_whitney(&[1.0; 10], &[1.0; 10]), 1.0);
+}
+
+#[test]
+fn compare_runs() {
+  let base = run(vec![result("same", samples(100.0)), result("slower", samples(100.0)),
+    result("faster", samples(100.0)), result("noise", samples(100.0)),
+    result("gone", samples(100.0))]);
+  let new = run(vec![result("same", samples(100.0)), result("slower", samples(120.0)),
+    result("faster", samples(50.0)), result("noise", samples(101.0)),
+    result("added", samples(100.0))]);
+  let comparison = compare(&base, &new, 0.02, 0.05);
+  let verdicts: Vec<(&str, Verdict)> = comparison.changes.iter()
+      .map(|c| (c.id.as_str(), c.verdict)).collect();
+  assert_eq!(verdicts, vec![("slower", Verdict::Regressed), ("noise", Verdict::Unchanged),
+    ("same", Verdict::Unchanged), ("faster", Verdict::Improved)]);
+  assert!((comparison.changes[0].change - 0.2).abs() < 1e-9);
+  assert_eq!(comparison.regressions().count(), 1);
+  assert_eq!(comparison.removed, vec!["gone"]);
+  assert_eq!(comparison.added, vec!["added"]);
+}
+
+#[test]
+fn exact_counts() {
+  let base = run(vec![result("same", vec![1000.0]), result("more", vec![1000.0]),
+    result("tiny", vec![1000.0])]);
+  let new = run(vec![result("same", vec![1000.0]), result("more", vec![1100.0]),
+    result("tiny", vec![1001.0])]);
+  let comparison = compare(&base, &new, 0.02, 0.05);
+  let verdicts: Vec<(&str, Verdict)> = comparison.changes.iter()
+      .map(|c| (c.id.as_str(), c.verdict)).collect();
+  assert_eq!(verdicts, vec![("more", Verdict::Regressed), ("tiny", Verdict::Unchanged),
+    ("same", Verdict::Unchanged)]);
+  assert_eq!(comparison.changes[0].p_value, 0.0);
+}
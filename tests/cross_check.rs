@@ -0,0 +1,69 @@
+// Copyright by Owen O'Malley 2024
+
+// Check that every variant in each benchmark group computes the same
+// result over several seeds and sizes.
+
+use rust_bench::cpp;
+use rust_bench::kernels::{branching, dispatch, dual, elements, interpreter, option, sliding, tail};
+
+const SEEDS: [u64; 3] = [0, 1, 2024];
+const SIZES: [usize; 6] = [0, 1, 2, 17, 1_000, 10_000];
+
+/// Run the verification for every seed and size.
+fn verify_all(verify: fn(u64, usize)) {
+  for seed in SEEDS {
+    for size in SIZES {
+      verify(seed, size);
+    }
+  }
+}
+
+#[test]
+fn branching_agrees() {
+  verify_all(branching::verify);
+}
+
+#[test]
+fn cpp_agrees() {
+  verify_all(cpp::verify);
+}
+
+#[test]
+fn dispatch_agrees() {
+  verify_all(dispatch::verify);
+}
+
+#[test]
+fn dual_agrees() {
+  verify_all(dual::verify);
+}
+
+#[test]
+fn elements_agrees() {
+  verify_all(elements::verify);
+}
+
+#[test]
+fn interpreter_agrees() {
+  verify_all(interpreter::verify);
+}
+
+#[test]
+fn option_agrees() {
+  verify_all(option::verify);
+}
+
+#[test]
+fn sliding_agrees() {
+  verify_all(sliding::verify);
+}
+
+#[test]
+fn tail_agrees() {
+  // The non-tail recursive variants need a deep stack without optimization.
+  std::thread::Builder::new().stack_size(256 << 20)
+      .spawn(|| verify_all(tail::verify))
+      .expect("can't spawn thread")
+      .join()
+      .expect("tail variants disagree");
+}
@@ -0,0 +1,81 @@
+// Copyright by Owen O'Malley 2024
+
+use rust_bench::{nearly_sorted_vec, normal_vec, reverse_sorted_vec, runs_vec, sorted_vec,
+                 zipf_vec, Shape};
+
+fn std_dev(data: &[i32]) -> f64 {
+  let mean = data.iter().map(|x| *x as f64).sum::<f64>() / data.len() as f64;
+  (data.iter().map(|x| (*x as f64 - mean).powi(2)).sum::<f64>() / data.len() as f64).sqrt()
+}
+
+#[test]
+fn shapes_stay_in_range() {
+  for shape in Shape::ALL {
+    for range in [0..10, -50..50, 0..1000] {
+      let data = shape.generate(range.clone(), 1000, 7);
+      assert_eq!(data.len(), 1000, "{shape}");
+      assert!(data.iter().all(|x| range.contains(x)), "{shape} left {range:?}");
+      assert_eq!(data, shape.generate(range.clone(), 1000, 7), "{shape} isn't deterministic");
+      assert_ne!(data, shape.generate(range, 1000, 8), "{shape} ignores the seed");
+    }
+  }
+}
+
+#[test]
+fn zipf_favors_the_start() {
+  let data = zipf_vec(5..15, 1.0, 10_000, 1);
+  let counts: Vec<usize> = (5..15).map(|v| data.iter().filter(|x| **x == v).count()).collect();
+  assert!(counts.windows(2).take(3).all(|pair| pair[0] > pair[1]), "{counts:?}");
+}
+
+#[test]
+#[should_panic(expected = "zipf needs a non-empty range")]
+fn zipf_rejects_empty_range() {
+  zipf_vec(3..3, 1.0, 10, 1);
+}
+
+#[test]
+fn normal_is_centered() {
+  let data = normal_vec(0..1000, 100.0, 10_000, 1);
+  let mean = data.iter().map(|x| *x as f64).sum::<f64>() / data.len() as f64;
+  assert!((mean - 499.5).abs() < 5.0, "mean {mean}");
+  assert!((std_dev(&data) - 100.0).abs() < 5.0, "std dev {}", std_dev(&data));
+}
+
+#[test]
+fn normal_shape_scales_with_range() {
+  let shape = Shape::Normal { spread: 0.15 };
+  let narrow = std_dev(&shape.generate(0..100, 10_000, 1));
+  let wide = std_dev(&shape.generate(0..10_000, 10_000, 1));
+  assert!((narrow - 15.0).abs() < 1.0, "narrow std dev {narrow}");
+  assert!((wide - 1500.0).abs() < 100.0, "wide std dev {wide}");
+}
+
+#[test]
+fn sorted_orders() {
+  let sorted = sorted_vec(0..100, 1000, 3);
+  assert!(sorted.is_sorted());
+  let mut reversed = reverse_sorted_vec(0..100, 1000, 3);
+  reversed.reverse();
+  assert_eq!(reversed, sorted);
+}
+
+#[test]
+fn nearly_sorted_permutes_sorted() {
+  let sorted = sorted_vec(0..1000, 1000, 3);
+  let mut nearly = nearly_sorted_vec(0..1000, 5, 1000, 3);
+  let out_of_place = nearly.iter().zip(&sorted).filter(|(a, b)| a != b).count();
+  assert!(out_of_place > 0 && out_of_place <= 100, "{out_of_place} out of place");
+  nearly.sort_unstable();
+  assert_eq!(nearly, sorted);
+  assert_eq!(nearly_sorted_vec(0..10, 5, 0, 3), Vec::<i32>::new());
+}
+
+#[test]
+fn runs_repeat_values() {
+  let data = runs_vec(0..1_000_000, 16, 1000, 3);
+  for chunk in data.chunks(16) {
+    assert!(chunk.iter().all(|x| *x == chunk[0]), "{chunk:?}");
+  }
+  assert!(data.chunks(16).zip(data.chunks(16).skip(1)).any(|(a, b)| a[0] != b[0]));
+}
@@ -0,0 +1,46 @@
+// Copyright by Owen O'Malley 2024
+
+use rust_bench::instructions::{parse_dump, running_on_valgrind, Events};
+
+const DUMP: &str = "# callgrind format
+version: 1
+creator: callgrind-3.22.0
+pid: 1234
+cmd:  target/release/deps/bench-0123 --bench
+part: 3
+
+desc: I1 cache: 32768 B, 64 B, 8-way associative
+desc: Trigger: Client Request: dispatch random/objs/8
+positions: line
+events: Ir Dr Dw I1mr D1mr D1mw ILmr DLmr DLmw
+summary: 2000 600 200 3 40 5 3 10 1
+
+fn=(1) rust_bench::kernels::dispatch::iter_objs
+0 2000 600 200 3 40 5 3 10 1
+
+totals: 2000 600 200 3 40 5 3 10 1
+";
+
+#[test]
+fn parses_the_dumps() {
+  let (trigger, events) = parse_dump(DUMP).unwrap();
+  assert_eq!(trigger, "dispatch random/objs/8");
+  assert_eq!(events, Events { instructions: 2000, data_reads: 600, data_writes: 200,
+    l1d_misses: 40, ll_misses: 10 });
+  let counters = events.per_element(2, 10);
+  assert_eq!(counters.instructions, Some(100.0));
+  assert_eq!(counters.l1d_accesses, Some(40.0));
+  assert_eq!(counters.l1d_misses, Some(2.0));
+  assert_eq!(counters.llc_misses, Some(0.5));
+  assert_eq!(counters.cycles, None);
+  // The dump at the end of the program isn't for a benchmark.
+  let end = DUMP.replace("Client Request: dispatch random/objs/8", "Program termination");
+  assert_eq!(parse_dump(&end), None);
+}
+
+#[test]
+fn client_requests_are_harmless_natively() {
+  // Outside of Valgrind, the requests do nothing and return the default.
+  assert!(!running_on_valgrind());
+  rust_bench::instructions::zero_stats();
+}
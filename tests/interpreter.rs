@@ -0,0 +1,37 @@
+// Copyright by Owen O'Malley 2024
+
+use rust_bench::kernels::interpreter::*;
+use rust_bench::patterns::Layout;
+
+#[test]
+fn runs_a_program() {
+  // add 5, store r1, mul 3, add r1, swap r2, inc r2, sub 7, neg, max r2,
+  // rotate 1, xor 3, load r1
+  let program = Program::new(vec![0, 5, 7, 1, 2, 3, 8, 1, 10, 2, 11, 2, 1, 7, 5, 0, 9, 2, 4, 1,
+                                  3, 3, 6, 1]).unwrap();
+  assert_eq!(program.len(), 12);
+  // The accumulator is 5, r1 is 5, and r2 is 21.
+  assert_eq!(run_match(&program), 31);
+  assert_eq!(run_table(&program, &OP_FUNCS), 31);
+  assert_eq!(run_vtables(&decode_vtables(&program)), 31);
+  assert_eq!(run_closures(&compile_closures(&program)), 31);
+}
+
+#[test]
+fn rejects_bad_programs() {
+  assert!(Program::new(Vec::new()).unwrap().is_empty());
+  assert_eq!(Program::new(vec![0, 5, 1]),
+             Err("The program has a partial instruction at byte 2".to_string()));
+  assert_eq!(Program::new(vec![0, 5, OPCODES as u8, 0]),
+             Err("Bad opcode 12 at instruction 1".to_string()));
+}
+
+#[test]
+fn programs_follow_the_layout() {
+  let program = program_from_layout(Layout::TickTock, 3, 6, 0);
+  assert_eq!(program.len(), 6);
+  let opcodes: Vec<u8> = program.code().iter().step_by(2).copied().collect();
+  assert_eq!(opcodes, [0, 1, 2, 0, 1, 2]);
+  let names: Vec<&str> = decode_vtables(&program).iter().map(|i| i.vtable.name).collect();
+  assert_eq!(names, ["add", "sub", "mul", "add", "sub", "mul"]);
+}
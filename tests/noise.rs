@@ -0,0 +1,50 @@
+// Copyright by Owen O'Malley 2024
+
+use rust_bench::noise::{busy_fractions, parse_cpu_list, parse_flag, parse_setting, Check,
+                        NoiseReport};
+
+#[test]
+fn cpu_lists() {
+  assert_eq!(parse_cpu_list("0-3,8\n"), vec![0, 1, 2, 3, 8]);
+  assert_eq!(parse_cpu_list("5"), vec![5]);
+  assert_eq!(parse_cpu_list(""), Vec::<usize>::new());
+}
+
+#[test]
+fn busy_from_proc_stat() {
+  let before = "cpu  300 0 100 1000 0 0 0 0 0 0\n\
+    cpu0 100 0 0 500 0 0 0 0 0 0\n\
+    cpu1 200 0 100 500 0 0 0 0 0 0\n\
+    intr 12345\n";
+  let after = "cpu  400 0 100 1200 0 0 0 0 0 0\n\
+    cpu0 100 0 0 600 0 0 0 0 0 0\n\
+    cpu1 300 0 100 600 0 0 0 0 0 0\n";
+  assert_eq!(busy_fractions(before, after), vec![(0, 0.0), (1, 0.5)]);
+}
+
+#[test]
+fn reports() {
+  let report = NoiseReport { pinned_cpu: Some(2), nice: None, strict: true,
+    checks: vec![Check { name: "governor".to_string(), value: "powersave".to_string(), noisy: true },
+      Check { name: "turbo".to_string(), value: "off".to_string(), noisy: false }] };
+  assert!(report.is_noisy());
+  assert_eq!(report.warnings(), vec!["governor: powersave"]);
+  assert_eq!(report.summary(), "pinned to CPU 2, governor powersave, turbo off");
+}
+
+#[test]
+fn settings() {
+  assert_eq!(parse_setting::<usize>("RUST_BENCH_PIN", " 3\n"), Ok(3));
+  assert_eq!(parse_setting::<i32>("RUST_BENCH_NICE", "-10"), Ok(-10));
+  assert_eq!(parse_setting::<usize>("RUST_BENCH_PIN", "cpu3"),
+             Err("bad RUST_BENCH_PIN \"cpu3\"".to_string()));
+}
+
+#[test]
+fn flags() {
+  assert!(parse_flag("1"));
+  assert!(parse_flag("yes"));
+  assert!(!parse_flag(""));
+  assert!(!parse_flag("0"));
+  assert!(!parse_flag(" 0\n"));
+}
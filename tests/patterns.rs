@@ -0,0 +1,35 @@
+// Copyright by Owen O'Malley 2024
+
+use rust_bench::patterns::{shuffled_period, shuffled_window, tick_tock, Layout};
+
+#[test]
+fn layouts_stay_in_classes() {
+  for layout in [Layout::Random, Layout::SortedBlocks, Layout::TickTock,
+                 Layout::ShuffledWindow { copies: 3 }, Layout::ShuffledPeriod { period: 7 },
+                 Layout::Markov { stay: 0.5 }] {
+    for classes in [1, 5] {
+      let ids = layout.generate(classes, 100, 1);
+      assert_eq!(ids.len(), 100, "{layout}");
+      assert!(ids.iter().all(|id| *id < classes), "{layout} with {classes} classes");
+    }
+    assert_eq!(layout.generate(3, 0, 1), Vec::<usize>::new(), "{layout}");
+  }
+}
+
+#[test]
+#[should_panic(expected = "at least one class")]
+fn tick_tock_needs_classes() {
+  tick_tock(0, 10);
+}
+
+#[test]
+#[should_panic(expected = "at least one copy")]
+fn shuffled_window_needs_copies() {
+  shuffled_window(3, 0, 10, 1);
+}
+
+#[test]
+#[should_panic(expected = "the period must be positive")]
+fn shuffled_period_needs_a_period() {
+  shuffled_period(3, 0, 10, 1);
+}
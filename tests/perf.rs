@@ -0,0 +1,30 @@
+// Copyright by Owen O'Malley 2024
+
+use std::hint::black_box;
+use rust_bench::perf::{CounterSet, Counters};
+
+#[test]
+fn derived_rates() {
+  let counters = Counters { instructions: Some(4.0), cycles: Some(2.0), branches: Some(1.0),
+    branch_misses: Some(0.25), ..Default::default() };
+  assert_eq!(counters.ipc(), Some(2.0));
+  assert_eq!(counters.branch_miss_rate(), Some(0.25));
+  assert_eq!(counters.to_string(), "4.000 instructions, 2.000 cycles, 0.250 branch misses \
+    per element, 25.00% of branches missed");
+  assert_eq!(Counters::default().branch_miss_rate(), None);
+}
+
+#[test]
+fn counts_or_fails_cleanly() {
+  // Many containers don't allow perf_event_open, which must be an error
+  // rather than a crash.
+  let Ok(mut set) = CounterSet::open() else {
+    return;
+  };
+  let data: Vec<u64> = (0..1000).collect();
+  let counters = set.measure(100, data.len() as u64, || black_box(&data).iter().sum::<u64>())
+      .unwrap();
+  if let Some(instructions) = counters.instructions {
+    assert!(instructions > 0.1, "{counters}");
+  }
+}
@@ -0,0 +1,44 @@
+// Copyright by Owen O'Malley 2024
+
+use rust_bench::patterns::shuffled_period;
+use rust_bench::predictor::{find_knee, ClassSweep, Knee, PredictorReport};
+
+fn sweep(classes: usize, periods: &[(usize, f64)], random_ns: f64) -> ClassSweep {
+  ClassSweep { classes, periods: periods.to_vec(), random_ns, knee: None }
+}
+
+#[test]
+fn periods_repeat_with_even_classes() {
+  let ids = shuffled_period(3, 12, 48, 0);
+  assert_eq!(&ids[..12], &ids[12..24]);
+  for class in 0..3 {
+    assert_eq!(ids[..12].iter().filter(|x| **x == class).count(), 4);
+  }
+}
+
+#[test]
+fn finds_the_knee() {
+  let periods = [(64, 1.5), (128, 1.5), (256, 2.0), (512, 5.0), (1024, 7.5), (2048, 8.0)];
+  assert_eq!(find_knee(&periods, 8.0), Some(Knee { below: 256, above: 512 }));
+  assert_eq!(Knee { below: 256, above: 1024 }.period(), 512.0);
+  // A dip below halfway after the first slow period moves the knee.
+  let dip = [(64, 1.5), (128, 6.0), (256, 2.0), (512, 7.0)];
+  assert_eq!(find_knee(&dip, 8.0), Some(Knee { below: 256, above: 512 }));
+  // Every period is fast, so the knee is beyond the grid.
+  assert_eq!(find_knee(&periods[..3], 8.0), None);
+  // Random isn't slower, so there is no knee.
+  assert_eq!(find_knee(&periods, 1.6), None);
+}
+
+#[test]
+fn estimates_the_capacity() {
+  let report = PredictorReport::new(vec![
+    sweep(2, &[(256, 1.0), (512, 4.0)], 4.0),
+    sweep(8, &[(512, 1.0), (1024, 4.0)], 4.0),
+    sweep(50, &[(1024, 1.0), (2048, 4.0)], 4.0),
+    sweep(1, &[(8, 1.0), (16, 1.0)], 1.0)]);
+  assert_eq!(report.sweeps[0].knee, Some(Knee { below: 256, above: 512 }));
+  assert_eq!(report.sweeps[3].knee, None);
+  assert!((report.capacity.unwrap() - 724.08).abs() < 0.01);
+  assert_eq!(PredictorReport::new(Vec::new()).capacity, None);
+}
@@ -0,0 +1,63 @@
+// Copyright by Owen O'Malley 2024
+
+use rust_bench::report::{format_time, rewrite};
+use rust_bench::results::{BenchResult, Run};
+
+fn result(id: &str, estimate: f64) -> BenchResult {
+  BenchResult { id: id.to_string(), group: id.to_string(), estimate,
+    lower: estimate - 1.0, upper: estimate + 1.0, ..Default::default() }
+}
+
+fn run(benchmarks: Vec<BenchResult>) -> Run {
+  Run { run_id: "20240501-000000".to_string(), benchmarks, ..Default::default() }
+}
+
+#[test]
+fn times_match_criterion() {
+  assert_eq!(format_time(0.5), "500.00 ps");
+  assert_eq!(format_time(345.5), "345.50 ns");
+  assert_eq!(format_time(8323.7), "8.3237 µs");
+  assert_eq!(format_time(52_132.0), "52.132 µs");
+}
+
+#[test]
+fn rewrites_only_measured_sections() {
+  let readme = "# Title\n\
+    <!-- results sliding -->\n\
+    * old line\n\
+    <!-- end results -->\n\
+    text\n\
+    <!-- results tail -->\n\
+    * painful\n\
+    <!-- end results -->\n";
+  let (text, updated) = rewrite(readme, &run(vec![result("sliding for", 700.0)])).unwrap();
+  assert_eq!(updated, vec!["sliding"]);
+  assert_eq!(text, "# Title\n\
+    <!-- results sliding -->\n\
+    * sliding for             time:   [699.00 ns 700.00 ns 701.00 ns]\n\
+    <!-- end results -->\n\
+    text\n\
+    <!-- results tail -->\n\
+    * painful\n\
+    <!-- end results -->\n");
+}
+
+#[test]
+fn groups_have_headings() {
+  let readme = "<!-- results tail -->\n<!-- end results -->\n";
+  let (text, _) = rewrite(readme, &run(vec![result("tail match", 52_132.0),
+    result("tail if", 346.0)])).unwrap();
+  assert_eq!(text, "<!-- results tail -->\n\
+    * painful\n\
+    \x20 * tail match              time:   [52.131 µs 52.132 µs 52.133 µs]\n\
+    * slow\n\
+    * fast\n\
+    \x20 * tail if                 time:   [345.00 ns 346.00 ns 347.00 ns]\n\
+    <!-- end results -->\n");
+}
+
+#[test]
+fn rejects_bad_markers() {
+  assert!(rewrite("<!-- results nope -->\n<!-- end results -->\n", &run(Vec::new())).is_err());
+  assert!(rewrite("<!-- results tail -->\n* painful\n", &run(Vec::new())).is_err());
+}
@@ -0,0 +1,74 @@
+// Copyright by Owen O'Malley 2024
+
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use rust_bench::machine::Fingerprint;
+use rust_bench::results::{collect, run_id, BenchResult, Measurement, Run};
+
+/// A Criterion directory with a group's benchmark, a lone benchmark, and
+/// the lone benchmark's base from an earlier run.
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/criterion");
+
+#[test]
+fn run_ids_are_utc_timestamps() {
+  assert_eq!(run_id(UNIX_EPOCH), "19700101-000000");
+  // 2024-02-29 23:59:59 UTC
+  assert_eq!(run_id(UNIX_EPOCH + Duration::from_secs(1_709_251_199)), "20240229-235959");
+  // 2000-03-01 12:34:56 UTC
+  assert_eq!(run_id(UNIX_EPOCH + Duration::from_secs(951_914_096)), "20000301-123456");
+}
+
+#[test]
+fn results_from_samples() {
+  let result = BenchResult::from_samples("tail if", "tail if", None, None, 100,
+                                         vec![1010.0, 1000.0, 1020.0], None);
+  assert_eq!((result.estimate, result.lower, result.upper), (1010.0, 1000.0, 1020.0));
+  assert_eq!(result.mean, 1010.0);
+  assert_eq!(result.std_dev, 10.0);
+  assert_eq!(result.elements, Some(100));
+  let exact = BenchResult::from_samples("tail if", "tail if", None, None, 100, vec![1000.0], None);
+  assert_eq!((exact.estimate, exact.std_dev), (1000.0, 0.0));
+}
+
+#[test]
+fn collects_criterion_results() {
+  let results = collect(Path::new(FIXTURE), None).unwrap();
+  let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+  assert_eq!(ids, ["dispatch random/objs/3", "tail if"]);
+  let objs = &results[0];
+  assert_eq!((objs.group.as_str(), objs.function.as_deref(), objs.parameter.as_deref()),
+             ("dispatch random", Some("objs"), Some("3")));
+  // The slope is the estimate when Criterion computed one.
+  assert_eq!((objs.estimate, objs.lower, objs.upper), (1000.0, 990.0, 1010.0));
+  assert_eq!((objs.mean, objs.median, objs.std_dev), (1020.0, 1010.0, 10.0));
+  assert_eq!((objs.elements, objs.elements_per_second), (Some(10_000), Some(1e10)));
+  assert_eq!(objs.samples, [1000.0, 1020.0]);
+  let tail = &results[1];
+  assert_eq!((tail.function.as_deref(), tail.parameter.as_deref()), (None, None));
+  // Without a slope, the estimate is the mean.
+  assert_eq!((tail.estimate, tail.lower, tail.upper), (50.0, 40.0, 60.0));
+  assert_eq!((tail.elements, tail.elements_per_second, tail.bytes), (None, None, None));
+  assert_eq!(tail.samples, [50.0]);
+  // The benchmarks measured before since are left out.
+  let future = SystemTime::now() + Duration::from_secs(3600);
+  assert!(collect(Path::new(FIXTURE), Some(future)).unwrap().is_empty());
+}
+
+#[test]
+fn writes_csv_rows() {
+  let machine = Fingerprint { cpu_model: "Test, CPU".to_string(), rustc: "rustc 1.0".to_string(),
+    ..Default::default() };
+  let run = Run::new(UNIX_EPOCH, None, 0, machine, Measurement::WallTime,
+                     collect(Path::new(FIXTURE), None).unwrap());
+  let csv = run.to_csv();
+  let lines: Vec<&str> = csv.lines().collect();
+  assert_eq!(lines.len(), 3);
+  assert!(lines[0].starts_with("run_id,config,seed,measurement,cpu_model,rustc,id,group,"));
+  let columns = lines[0].split(',').count();
+  assert!(lines[1].starts_with("19700101-000000,,0,wall time,\"Test, CPU\",rustc 1.0,\
+                                dispatch random/objs/3,dispatch random,objs,3,ns,1000,990,1010,"));
+  assert!(lines[2].contains(",tail if,tail if,,,ns,50,40,60,"));
+  // The quoted comma in the cpu model is the only extra separator.
+  assert_eq!(lines[1].split(',').count(), columns + 1);
+  assert_eq!(lines[2].split(',').count(), columns + 1);
+}
@@ -0,0 +1,44 @@
+// Copyright by Owen O'Malley 2024
+
+use rust_bench::results::{BenchResult, Measurement, Run};
+use rust_bench::seeds::spread;
+
+fn result(id: &str, estimate: f64, half_width: f64) -> BenchResult {
+  BenchResult { id: id.to_string(), group: id.to_string(), estimate,
+    lower: estimate - half_width, upper: estimate + half_width, ..Default::default() }
+}
+
+fn run(seed: u64, benchmarks: Vec<BenchResult>) -> Run {
+  Run { seed, benchmarks, ..Default::default() }
+}
+
+#[test]
+fn separates_seeds_from_noise() {
+  let runs = [run(0, vec![result("stable", 100.0, 2.0), result("data", 100.0, 1.0)]),
+    run(1, vec![result("stable", 101.0, 2.0), result("data", 120.0, 1.0)]),
+    run(2, vec![result("stable", 102.0, 2.0), result("data", 140.0, 1.0),
+                result("new", 50.0, 1.0)])];
+  let spreads = spread(&runs);
+  let ids: Vec<&str> = spreads.iter().map(|s| s.id.as_str()).collect();
+  assert_eq!(ids, vec!["data", "new", "stable"]);
+  let data = &spreads[0];
+  assert_eq!(data.seeds, vec![0, 1, 2]);
+  assert_eq!(data.measurement, Measurement::WallTime);
+  assert_eq!(data.mean, 120.0);
+  assert_eq!(data.seed_std_dev, 20.0);
+  assert_eq!((data.min, data.max, data.noise), (100.0, 140.0, 1.0));
+  assert!(data.depends_on_data());
+  assert_eq!(spreads[1].seed_std_dev, 0.0);
+  let stable = &spreads[2];
+  assert_eq!(stable.seed_std_dev, 1.0);
+  assert!(!stable.depends_on_data());
+}
+
+#[test]
+fn keeps_the_measurement() {
+  let runs = [run(0, vec![result("a", 10.0, 0.0)]), run(1, vec![result("a", 12.0, 0.0)])]
+      .map(|run| Run { measurement: Measurement::Instructions, ..run });
+  let spreads = spread(&runs);
+  assert_eq!(spreads[0].measurement, Measurement::Instructions);
+  assert_eq!(spreads[0].mean, 11.0);
+}
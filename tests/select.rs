@@ -0,0 +1,45 @@
+// Copyright by Owen O'Malley 2024
+
+use std::ops::Bound;
+use rust_bench::select::{Bench, ParamRange, Selection};
+
+fn bench<'a>(category: &'a str, group: &'a str, variant: Option<&'a str>,
+             parameter: Option<&'a str>) -> Bench<'a> {
+  Bench { category, group, variant, parameter }
+}
+
+#[test]
+fn parse_ranges() {
+  let range: ParamRange = "8..=12".parse().unwrap();
+  assert_eq!(range, ParamRange { low: Bound::Included(8.0), high: Bound::Included(12.0) });
+  assert!(range.contains(8.0) && range.contains(12.0) && !range.contains(13.0));
+  let range: ParamRange = "8..12".parse().unwrap();
+  assert!(range.contains(11.5) && !range.contains(12.0));
+  let range: ParamRange = "..=0.5".parse().unwrap();
+  assert!(range.contains(-3.0) && range.contains(0.5) && !range.contains(0.9));
+  let range: ParamRange = "50..".parse().unwrap();
+  assert!(range.contains(160.0) && !range.contains(40.0));
+  let range: ParamRange = "10".parse().unwrap();
+  assert!(range.contains(10.0) && !range.contains(11.0));
+  assert!("a..3".parse::<ParamRange>().is_err());
+}
+
+#[test]
+fn select_by_structure() {
+  let selection = Selection { categories: vec!["dispatch".to_string()],
+    groups: vec!["dispatch random".to_string()], variants: vec!["objs".to_string()],
+    parameters: Some("8..=12".parse().unwrap()), list: false };
+  assert!(selection.wants_category("dispatch"));
+  assert!(!selection.wants_category("branching"));
+  assert!(selection.wants(&bench("dispatch", "dispatch random", Some("objs"), Some("8"))));
+  assert!(!selection.wants(&bench("dispatch", "dispatch random", Some("objs"), Some("50"))));
+  assert!(!selection.wants(&bench("dispatch", "dispatch rndrn", Some("objs"), Some("10"))));
+  assert!(!selection.wants(&bench("dispatch", "dispatch random", None, Some("10"))));
+  // Without a parameter range, the lone benchmarks match too.
+  let selection = Selection { categories: vec!["branching".to_string()], ..Selection::default() };
+  assert!(selection.wants(&bench("branching", "lookup array", None, None)));
+  assert!(!selection.wants(&bench("tail", "tail if", None, None)));
+  let range = Selection { parameters: Some("1..".parse().unwrap()), ..Selection::default() };
+  assert!(!range.wants(&bench("branching", "branching shaped", Some("if"), Some("sorted"))));
+  assert!(Selection::default().wants(&bench("cpp", "cpp sum", None, None)));
+}